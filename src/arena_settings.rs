@@ -1,3 +1,4 @@
+use crate::ship::Ship;
 use std::mem::{self, MaybeUninit};
 
 #[derive(Debug, Copy, Clone)]
@@ -724,6 +725,11 @@ impl ShipSettings {
 }
 
 impl ArenaSettings {
+    // Spectators have no ship settings.
+    pub fn ship(&self, ship: Ship) -> Option<&ShipSettings> {
+        self.ship_settings.get(ship.network_value() as usize)
+    }
+
     pub fn parse(data: &[u8]) -> Option<ArenaSettings> {
         if data.len() < 1428 {
             return None;
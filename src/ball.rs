@@ -0,0 +1,200 @@
+use crate::arena_settings::ArenaSettings;
+use crate::clock::ServerTick;
+use crate::map::Map;
+use crate::math::{Position, Velocity};
+use crate::net::packet::s2c::PowerballPositionMessage;
+use crate::player::{PlayerId, PlayerManager};
+use crate::ship::Ship;
+use std::collections::HashMap;
+
+// Friction is tracked in millionths of the ball's velocity.
+const BALL_FRICTION_START: i32 = 1_000_000;
+// Limit on how far ahead a ball is simulated so a bad timestamp can't stall the client.
+const MAX_SIMULATION_TICKS: i32 = 6000;
+
+#[derive(Clone, Debug)]
+pub struct Ball {
+    pub id: u8,
+    // Pixel position and velocity (pixels per 10 seconds) at the time of the last update.
+    pub position: Position,
+    pub velocity: Velocity,
+    // The player holding the ball. When the ball is loose this is None and last_owner is whoever fired it.
+    pub carrier: Option<PlayerId>,
+    pub last_owner: PlayerId,
+    pub timestamp: ServerTick,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GoalRecord {
+    pub goals: u32,
+    // Team points from the last goal message for this frequency.
+    pub points: u32,
+}
+
+pub struct BallManager {
+    pub balls: HashMap<u8, Ball>,
+    pub goals: HashMap<u16, GoalRecord>,
+}
+
+impl BallManager {
+    pub fn new() -> BallManager {
+        BallManager {
+            balls: HashMap::new(),
+            goals: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, ball_id: u8) -> Option<&Ball> {
+        self.balls.get(&ball_id)
+    }
+
+    // A zero timestamp means the ball is being carried by the owner.
+    pub fn on_position(&mut self, message: &PowerballPositionMessage) {
+        let carried = message.timestamp.value() == 0 && message.owner_id != PlayerId::invalid();
+
+        self.balls.insert(
+            message.ball_id,
+            Ball {
+                id: message.ball_id,
+                position: Position::new(message.x as u32, message.y as u32),
+                velocity: Velocity::new(message.x_velocity as i32, message.y_velocity as i32),
+                carrier: if carried {
+                    Some(message.owner_id)
+                } else {
+                    None
+                },
+                last_owner: message.owner_id,
+                timestamp: message.timestamp,
+            },
+        );
+    }
+
+    pub fn on_goal(&mut self, frequency: u16, points: u32) {
+        let record = self.goals.entry(frequency).or_default();
+
+        record.goals += 1;
+        record.points = points;
+    }
+
+    pub fn goals_for(&self, frequency: u16) -> u32 {
+        self.goals
+            .get(&frequency)
+            .map(|record| record.goals)
+            .unwrap_or(0)
+    }
+
+    pub fn on_player_leave(&mut self, player_id: PlayerId) {
+        for ball in self.balls.values_mut() {
+            if ball.carrier == Some(player_id) {
+                ball.carrier = None;
+            }
+        }
+    }
+
+    // Estimates where the ball is at the given server tick. Carried balls are wherever the carrier is.
+    pub fn extrapolate(
+        &self,
+        ball_id: u8,
+        now: ServerTick,
+        settings: &ArenaSettings,
+        map: &Map,
+        player_manager: &PlayerManager,
+    ) -> Option<Position> {
+        let ball = self.balls.get(&ball_id)?;
+
+        if let Some(carrier) = ball.carrier {
+            return Some(
+                player_manager
+                    .get(&carrier)
+                    .map(|player| player.position)
+                    .unwrap_or(ball.position),
+            );
+        }
+
+        let ticks = now.diff(&ball.timestamp).clamp(0, MAX_SIMULATION_TICKS);
+
+        // Friction comes from the ship that fired the ball.
+        let ship = player_manager
+            .get(&ball.last_owner)
+            .map(|player| player.ship)
+            .unwrap_or(Ship::Warbird);
+        let friction = settings
+            .ship(ship)
+            .unwrap_or(&settings.ship_settings[0])
+            .powerball_friction;
+
+        Some(simulate(
+            ball,
+            ticks,
+            friction,
+            settings.powerball_bounce,
+            map,
+        ))
+    }
+}
+
+impl Default for BallManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Moves the ball one tick at a time in thousandths of a pixel. Velocity is reduced by the arena's
+// powerball friction each tick until the ball stops.
+fn simulate(ball: &Ball, ticks: i32, friction_step: u16, bounce: bool, map: &Map) -> Position {
+    let mut x = ball.position.x as i64 * 1000;
+    let mut y = ball.position.y as i64 * 1000;
+    let mut x_velocity = ball.velocity.x as i64;
+    let mut y_velocity = ball.velocity.y as i64;
+    let mut friction = BALL_FRICTION_START as i64;
+    let friction_step = friction_step as i64;
+
+    for _ in 0..ticks {
+        if friction <= 0 {
+            break;
+        }
+
+        let step_x = x_velocity * friction / BALL_FRICTION_START as i64;
+        let step_y = y_velocity * friction / BALL_FRICTION_START as i64;
+
+        let next_x = x + step_x;
+        if is_solid_pixel(map, next_x, y) {
+            if bounce {
+                x_velocity = -x_velocity;
+            } else {
+                x_velocity = 0;
+                y_velocity = 0;
+            }
+        } else {
+            x = next_x;
+        }
+
+        let next_y = y + step_y;
+        if is_solid_pixel(map, x, next_y) {
+            if bounce {
+                y_velocity = -y_velocity;
+            } else {
+                x_velocity = 0;
+                y_velocity = 0;
+            }
+        } else {
+            y = next_y;
+        }
+
+        friction -= friction_step;
+    }
+
+    Position::new((x / 1000) as u32, (y / 1000) as u32)
+}
+
+// Positions are in thousandths of a pixel. Anything outside the map counts as solid.
+fn is_solid_pixel(map: &Map, x: i64, y: i64) -> bool {
+    let tile_x = x / 16000;
+    let tile_y = y / 16000;
+
+    if !(0..1024).contains(&tile_x) || !(0..1024).contains(&tile_y) {
+        return true;
+    }
+
+    map.is_solid(tile_x as u16, tile_y as u16)
+}
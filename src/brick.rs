@@ -0,0 +1,78 @@
+use crate::clock::ServerTick;
+use crate::map::Map;
+use crate::net::packet::s2c::BrickDropMessage;
+
+#[derive(Clone, Debug)]
+pub struct Brick {
+    pub id: u16,
+    pub frequency: u16,
+    pub timestamp: ServerTick,
+    // Every tile covered by the brick. Bricks are straight horizontal or vertical lines.
+    pub tiles: Vec<(u16, u16)>,
+}
+
+// Live bricks laid on top of the map. Bricks block enemy frequencies but let the owning team through.
+pub struct BrickManager {
+    pub bricks: Vec<Brick>,
+}
+
+impl BrickManager {
+    pub fn new() -> BrickManager {
+        BrickManager { bricks: Vec::new() }
+    }
+
+    pub fn on_drop(&mut self, message: &BrickDropMessage) {
+        let mut tiles = Vec::new();
+
+        let (x1, x2) = (message.x1.min(message.x2), message.x1.max(message.x2));
+        let (y1, y2) = (message.y1.min(message.y2), message.y1.max(message.y2));
+
+        for y in y1..=y2 {
+            for x in x1..=x2 {
+                tiles.push((x, y));
+            }
+        }
+
+        // The server reuses ids, so a new brick replaces any old one with the same id.
+        self.bricks.retain(|brick| brick.id != message.brick_id);
+        self.bricks.push(Brick {
+            id: message.brick_id,
+            frequency: message.frequency,
+            timestamp: message.timestamp,
+            tiles,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.bricks.clear();
+    }
+
+    // Removes bricks that have been alive for at least brick_time ticks.
+    pub fn expire(&mut self, now: ServerTick, brick_time: i32) {
+        self.bricks
+            .retain(|brick| now.diff(&brick.timestamp) < brick_time);
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> Option<&Brick> {
+        self.bricks
+            .iter()
+            .find(|brick| brick.tiles.contains(&(x, y)))
+    }
+
+    // Bricks only block ships that aren't on the brick's frequency.
+    pub fn is_blocking(&self, x: u16, y: u16, frequency: u16) -> bool {
+        self.get(x, y)
+            .is_some_and(|brick| brick.frequency != frequency)
+    }
+
+    // Map solidity with bricks applied for a ship on the given frequency.
+    pub fn is_solid(&self, map: &Map, x: u16, y: u16, frequency: u16) -> bool {
+        map.is_solid(x, y) || self.is_blocking(x, y, frequency)
+    }
+}
+
+impl Default for BrickManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
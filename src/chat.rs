@@ -0,0 +1,336 @@
+use crate::clock::LocalTick;
+use crate::net::packet::c2s::SendChatMessage;
+use crate::net::packet::s2c::ChatKind;
+use crate::player::PlayerId;
+use std::collections::{HashMap, VecDeque};
+
+// Longest message text the client will send. The server truncates anything longer.
+pub const MAX_CHAT_LENGTH: usize = 247;
+
+// Allows up to `messages` to be sent within any window of `ticks`.
+#[derive(Copy, Clone, Debug)]
+pub struct RateLimit {
+    pub messages: usize,
+    pub ticks: i32,
+}
+
+impl RateLimit {
+    pub fn new(messages: usize, ticks: i32) -> RateLimit {
+        RateLimit { messages, ticks }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChatPriority {
+    High,
+    Normal,
+}
+
+struct QueuedChat {
+    kind: ChatKind,
+    sound: u8,
+    target_id: PlayerId,
+    text: String,
+}
+
+// Keeps track of recent send times so a rate limit can be checked.
+struct SendHistory {
+    limit: RateLimit,
+    sent: VecDeque<LocalTick>,
+}
+
+impl SendHistory {
+    fn new(limit: RateLimit) -> SendHistory {
+        SendHistory {
+            limit,
+            sent: VecDeque::new(),
+        }
+    }
+
+    fn is_limited(&mut self, now: LocalTick) -> bool {
+        while let Some(tick) = self.sent.front() {
+            if now.diff(tick) < self.limit.ticks {
+                break;
+            }
+            self.sent.pop_front();
+        }
+
+        self.sent.len() >= self.limit.messages
+    }
+}
+
+// Outbound chat is queued here and released by the client at a rate that won't trigger flood protection.
+// Private messages are sent ahead of everything else so command replies aren't stuck behind announcements.
+pub struct ChatQueue {
+    high: VecDeque<QueuedChat>,
+    normal: VecDeque<QueuedChat>,
+
+    default_limit: RateLimit,
+    kind_history: HashMap<ChatKind, SendHistory>,
+    // Flood protection counts all chat from a player, so there is a limit across every kind as well.
+    total_history: SendHistory,
+}
+
+impl ChatQueue {
+    pub fn new() -> ChatQueue {
+        ChatQueue {
+            high: VecDeque::new(),
+            normal: VecDeque::new(),
+            default_limit: RateLimit::new(3, 200),
+            kind_history: HashMap::new(),
+            total_history: SendHistory::new(RateLimit::new(5, 200)),
+        }
+    }
+
+    pub fn set_limit(&mut self, kind: ChatKind, limit: RateLimit) {
+        match self.kind_history.get_mut(&kind) {
+            Some(history) => history.limit = limit,
+            None => {
+                self.kind_history.insert(kind, SendHistory::new(limit));
+            }
+        }
+    }
+
+    // Used for any chat kind that doesn't have its own limit.
+    pub fn set_default_limit(&mut self, limit: RateLimit) {
+        self.default_limit = limit;
+    }
+
+    pub fn set_total_limit(&mut self, limit: RateLimit) {
+        self.total_history.limit = limit;
+    }
+
+    pub fn len(&self) -> usize {
+        self.high.len() + self.normal.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.high.clear();
+        self.normal.clear();
+    }
+
+    pub fn push(&mut self, message: &SendChatMessage) {
+        let priority = match message.kind {
+            ChatKind::Private | ChatKind::RemotePrivate => ChatPriority::High,
+            _ => ChatPriority::Normal,
+        };
+
+        self.push_with_priority(message, priority);
+    }
+
+    // Long text is split into multiple messages. Remote private and channel messages keep their
+    // ":target:" or "1;" prefix on every part.
+    pub fn push_with_priority(&mut self, message: &SendChatMessage, priority: ChatPriority) {
+        let (prefix, body) = split_prefix(message.kind, &message.text);
+        let max_length = MAX_CHAT_LENGTH.saturating_sub(prefix.len()).max(1);
+
+        let queue = match priority {
+            ChatPriority::High => &mut self.high,
+            ChatPriority::Normal => &mut self.normal,
+        };
+
+        for part in split_text(body, max_length) {
+            queue.push_back(QueuedChat {
+                kind: message.kind,
+                sound: message.sound,
+                target_id: message.target_id,
+                text: format!("{}{}", prefix, part),
+            });
+        }
+    }
+
+    // Sends every message that is currently allowed by the rate limits.
+    pub fn send_ready<F>(&mut self, now: LocalTick, mut send: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&SendChatMessage) -> anyhow::Result<()>,
+    {
+        while let Some(chat) = self.pop_ready(now) {
+            let message = SendChatMessage {
+                kind: chat.kind,
+                sound: chat.sound,
+                target_id: chat.target_id,
+                text: chat.text.as_str().into(),
+            };
+
+            send(&message)?;
+        }
+
+        Ok(())
+    }
+
+    fn pop_ready(&mut self, now: LocalTick) -> Option<QueuedChat> {
+        if self.is_empty() || self.total_history.is_limited(now) {
+            return None;
+        }
+
+        // Only the first message of each kind can be sent so messages of the same kind stay in order.
+        let mut blocked: Vec<ChatKind> = Vec::new();
+        let mut found = None;
+
+        for (queue_index, queue) in [&self.high, &self.normal].into_iter().enumerate() {
+            for (index, chat) in queue.iter().enumerate() {
+                if blocked.contains(&chat.kind) {
+                    continue;
+                }
+
+                let default_limit = self.default_limit;
+                let history = self
+                    .kind_history
+                    .entry(chat.kind)
+                    .or_insert_with(|| SendHistory::new(default_limit));
+
+                if history.is_limited(now) {
+                    blocked.push(chat.kind);
+                    continue;
+                }
+
+                found = Some((queue_index, index));
+                break;
+            }
+
+            if found.is_some() {
+                break;
+            }
+        }
+
+        let (queue_index, index) = found?;
+        let queue = match queue_index {
+            0 => &mut self.high,
+            _ => &mut self.normal,
+        };
+        let chat = queue.remove(index)?;
+
+        if let Some(history) = self.kind_history.get_mut(&chat.kind) {
+            history.sent.push_back(now);
+        }
+        self.total_history.sent.push_back(now);
+
+        Some(chat)
+    }
+}
+
+impl Default for ChatQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Sender and body split out of the text of an incoming remote private, squad or channel message.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChatText<'a> {
+    pub sender_name: Option<&'a str>,
+    pub squad: Option<&'a str>,
+    pub channel: Option<u8>,
+    pub body: &'a str,
+}
+
+// Remote private messages arrive as "(name)>body", squad messages as "(#squad)(name)>body"
+// and channel messages as "1:name> body". Other kinds and unrecognized text are returned as the body.
+pub fn parse_chat_text(kind: ChatKind, text: &str) -> ChatText<'_> {
+    let mut result = ChatText {
+        sender_name: None,
+        squad: None,
+        channel: None,
+        body: text,
+    };
+
+    match kind {
+        ChatKind::RemotePrivate => {
+            let mut rest = text;
+
+            if let Some((squad, after)) = rest
+                .strip_prefix("(#")
+                .and_then(|rest| rest.split_once(")("))
+            {
+                result.squad = Some(squad);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('(') {
+                rest = after;
+            } else {
+                return result;
+            }
+
+            if let Some((name, body)) = rest.split_once(")>") {
+                result.sender_name = Some(name);
+                result.body = body;
+            } else {
+                result.squad = None;
+            }
+        }
+        ChatKind::Channel => {
+            let Some((channel, rest)) = text.split_once(':') else {
+                return result;
+            };
+            let Ok(channel) = channel.parse::<u8>() else {
+                return result;
+            };
+            let Some((name, body)) = rest.split_once("> ") else {
+                return result;
+            };
+
+            result.sender_name = Some(name);
+            result.channel = Some(channel);
+            result.body = body;
+        }
+        _ => {}
+    }
+
+    result
+}
+
+// Separates the addressing prefix from the text that can be split.
+fn split_prefix(kind: ChatKind, text: &str) -> (&str, &str) {
+    let end = match kind {
+        ChatKind::RemotePrivate => text
+            .strip_prefix(':')
+            .and_then(|rest| rest.find(':'))
+            .map(|index| index + 2),
+        ChatKind::Channel => text.find(';').map(|index| index + 1),
+        _ => None,
+    };
+
+    match end {
+        Some(end) => text.split_at(end),
+        None => ("", text),
+    }
+}
+
+// Splits text into parts no longer than max_length bytes, breaking on spaces where possible.
+fn split_text(text: &str, max_length: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut remaining = text.trim_end();
+
+    while remaining.len() > max_length {
+        let mut end = max_length;
+        while !remaining.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        if end == 0 {
+            end = remaining.chars().next().map_or(1, |c| c.len_utf8());
+        }
+
+        // Break at the last space that fits. A single word longer than the limit is cut.
+        let split = if remaining[end..].starts_with(' ') {
+            end
+        } else {
+            match remaining[..end].rfind(' ') {
+                Some(space) if space > 0 => space,
+                _ => end,
+            }
+        };
+
+        parts.push(remaining[..split].trim_end());
+        remaining = remaining[split..].trim_start();
+    }
+
+    if !remaining.is_empty() || parts.is_empty() {
+        parts.push(remaining);
+    }
+
+    parts
+}
@@ -0,0 +1,152 @@
+use crate::chat::parse_chat_text;
+use crate::net::packet::s2c::{ChatKind, ChatMessage};
+use crate::player::PlayerManager;
+use std::fs::{DirBuilder, File, OpenOptions};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Writes chat to zones/<zone>/chat/<arena>/<yyyy-mm-dd>.log. A new file is started each day (UTC)
+// and whenever the arena changes.
+pub struct ChatLogger {
+    pub zone: String,
+    // Also log arena, warning and error messages sent by the server.
+    pub include_arena: bool,
+
+    current_path: String,
+    file: Option<File>,
+}
+
+impl ChatLogger {
+    pub fn new(zone: &str, include_arena: bool) -> ChatLogger {
+        ChatLogger {
+            zone: zone.to_owned(),
+            include_arena,
+            current_path: String::new(),
+            file: None,
+        }
+    }
+
+    pub fn log_message(
+        &mut self,
+        arena: &str,
+        message: &ChatMessage,
+        player_manager: &PlayerManager,
+    ) -> anyhow::Result<()> {
+        let is_arena = matches!(
+            message.kind,
+            ChatKind::Arena | ChatKind::Error | ChatKind::Warning
+        );
+
+        if is_arena && !self.include_arena {
+            return Ok(());
+        }
+
+        let text = parse_chat_text(message.kind, &message.message);
+
+        let sender_name = match message.kind {
+            ChatKind::Public
+            | ChatKind::PublicMacro
+            | ChatKind::Team
+            | ChatKind::Frequency
+            | ChatKind::Private => player_manager
+                .get(&message.sender)
+                .map(|player| player.name.as_str()),
+            _ => text.sender_name,
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let (date, time) = format_timestamp(now);
+
+        let line = match (sender_name, text.channel, text.squad) {
+            (Some(name), Some(channel), _) => {
+                format!(
+                    "[{} {}] {:?} {}:{}> {}",
+                    date, time, message.kind, channel, name, text.body
+                )
+            }
+            (Some(name), None, Some(squad)) => {
+                format!(
+                    "[{} {}] {:?} (#{}) {}> {}",
+                    date, time, message.kind, squad, name, text.body
+                )
+            }
+            (Some(name), None, None) => {
+                format!(
+                    "[{} {}] {:?} {}> {}",
+                    date, time, message.kind, name, text.body
+                )
+            }
+            (None, _, _) => format!("[{} {}] {:?} {}", date, time, message.kind, text.body),
+        };
+
+        let file = self.open(arena, &date)?;
+        writeln!(file, "{}", line)?;
+
+        Ok(())
+    }
+
+    // Returns the file for the arena and day, opening a new one if either has changed.
+    fn open(&mut self, arena: &str, date: &str) -> anyhow::Result<&mut File> {
+        let directory = format!("zones/{}/chat/{}", self.zone, sanitize(arena));
+        let path = format!("{}/{}.log", directory, date);
+
+        if self.file.is_none() || self.current_path != path {
+            DirBuilder::new().recursive(true).create(&directory)?;
+
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+            self.file = Some(file);
+            self.current_path = path;
+        }
+
+        Ok(self.file.as_mut().unwrap())
+    }
+}
+
+// Arena names come from the server, so keep them from escaping the log directory.
+fn sanitize(arena: &str) -> String {
+    let name: String = arena
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if name.is_empty() {
+        "public".to_owned()
+    } else {
+        name
+    }
+}
+
+// Formats unix seconds as a UTC date and time.
+fn format_timestamp(seconds: u64) -> (String, String) {
+    let days = (seconds / 86400) as i64;
+    let seconds_of_day = seconds % 86400;
+
+    // Converts days since the unix epoch to a civil date.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let date = format!("{:04}-{:02}-{:02}", year, month, day);
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds_of_day / 3600,
+        (seconds_of_day / 60) % 60,
+        seconds_of_day % 60
+    );
+
+    (date, time)
+}
@@ -130,6 +130,7 @@ pub fn vie_checksum(key: u32) -> u32 {
     return csum;
 }
 
+#[allow(dead_code)]
 const CRC8_TABLE: [u8; 256] = [
     0x00, 0x5e, 0xbc, 0xe2, 0x61, 0x3f, 0xdd, 0x83, 0xc2, 0x9c, 0x7e, 0x20, 0xa3, 0xfd, 0x1f, 0x41,
     0x9d, 0xc3, 0x21, 0x7f, 0xfc, 0xa2, 0x40, 0x1e, 0x5f, 0x01, 0xe3, 0xbd, 0x3e, 0x60, 0x82, 0xdc,
@@ -149,6 +150,7 @@ const CRC8_TABLE: [u8; 256] = [
     0x74, 0x2a, 0xc8, 0x96, 0x15, 0x4b, 0xa9, 0xf7, 0xb6, 0xe8, 0x0a, 0x54, 0xd7, 0x89, 0x6b, 0x35,
 ];
 
+#[allow(dead_code)]
 pub fn crc8(data: &[u8]) -> u8 {
     let mut crc: u8 = 0;
 
@@ -159,6 +161,7 @@ pub fn crc8(data: &[u8]) -> u8 {
     crc
 }
 
+#[allow(dead_code)]
 pub fn crc8_repeat(data: u8, len: usize) -> u8 {
     let mut crc: u8 = 0;
 
@@ -215,6 +218,7 @@ pub fn crc32(data: &[u8]) -> u32 {
     crc
 }
 
+#[allow(dead_code)]
 const CRC32_REFLECTED_TABLE: [u32; 256] = [
     0x00000000, 0x77073096, 0xee0e612c, 0x990951ba, 0x076dc419, 0x706af48f, 0xe963a535, 0x9e6495a3,
     0x0edb8832, 0x79dcb8a4, 0xe0d5e91e, 0x97d2d988, 0x09b64c2b, 0x7eb17cbd, 0xe7b82d07, 0x90bf1d91,
@@ -251,6 +255,7 @@ const CRC32_REFLECTED_TABLE: [u32; 256] = [
 ];
 
 // This is used for optional crc included at the end of the security packet.
+#[allow(dead_code)]
 pub fn crc32_map(data: &[u8]) -> u32 {
     let mut crc: u32 = 0xFFFFFFFF;
 
@@ -1,18 +1,34 @@
 use crate::arena_settings::ArenaSettings;
+use crate::ball::BallManager;
+use crate::brick::BrickManager;
+use crate::chat::{ChatQueue, parse_chat_text};
+use crate::chat_log::ChatLogger;
 use crate::checksum;
 use crate::clock::*;
-use crate::map::Map;
+use crate::door::Doors;
+use crate::event::BotEvent;
+use crate::flag::FlagManager;
+use crate::flight::{self, MovementGoal, ShipInput, ShipState, Turn};
+use crate::koth::Koth;
+use crate::map::{Map, TILE_ID_SAFE};
 use crate::math::{Position, Velocity};
 use crate::net::connection::{Connection, ConnectionState};
 use crate::net::packet::bi::*;
 use crate::net::packet::c2s::*;
 use crate::net::packet::s2c::*;
+use crate::observer::Observer;
 use crate::player::*;
+use crate::prediction::{
+    self, BOUNCE_FACTOR_BASE, DEFAULT_SHIP_RADIUS, MAX_PREDICTION_TICKS, PositionSample,
+};
+use crate::prize::Prize;
 use crate::ship::Ship;
-use crate::weapon::WeaponData;
+use crate::weapon::{FireKind, Target, WeaponData, WeaponKind, WeaponManager};
 
 use miniz_oxide::inflate::decompress_to_vec_zlib;
+use std::collections::VecDeque;
 use std::fs::{self, DirBuilder};
+use std::net::{IpAddr, SocketAddr};
 
 fn build_zone_directory(zone: &str) -> anyhow::Result<()> {
     DirBuilder::new()
@@ -25,18 +41,61 @@ fn get_zone_path(zone: &str, filename: &str) -> String {
     format!("zones/{}/{}", zone, filename)
 }
 
+// Maximum number of redirects that will be followed before the chain is considered a loop.
+const MAX_REDIRECTS: u32 = 5;
+// Redirect count is reset after this many ticks without a redirect.
+const REDIRECT_RESET_TICKS: i32 = 3000;
+// How long the observer watches each player while we are spectating.
+const OBSERVER_ROTATE_TICKS: i32 = 300;
+// Most ticks of our own movement simulated in one call to tick. Anything more is dropped after a stall.
+const MAX_SIMULATION_TICKS: i32 = 100;
+
 pub struct Client {
     pub connection: Connection,
     pub map: Map,
     pub settings: Option<Box<ArenaSettings>>,
     pub last_position_tick: LocalTick,
     pub player_manager: PlayerManager,
+    // Our own player id in the current arena.
+    pub player_id: PlayerId,
+    pub flag_manager: FlagManager,
+    pub ball_manager: BallManager,
+    pub koth: Koth,
+    pub brick_manager: BrickManager,
+    pub doors: Doors,
+    pub weapon_manager: WeaponManager,
+    pub chat_queue: ChatQueue,
+    pub chat_logger: Option<ChatLogger>,
+    pub observer: Observer,
+    // Set by the server when someone watching us is allowed to see our extra position data. Our position
+    // packets include it while this is set.
+    pub extra_position_info: bool,
+    // Our own ship as we are flying it.
+    pub ship_state: ShipState,
+    pub last_simulation_tick: LocalTick,
+    // Set after we die, when the ship should be placed back at its spawn.
+    pub respawn_tick: Option<LocalTick>,
+    // Steering the ship is doing by itself. Setting any input directly cancels it.
+    pub movement_goal: Option<MovementGoal>,
 
     pub username: String,
     pub password: String,
     pub zone: String,
+    pub arena_request: ArenaRequest,
+    // Name of the arena we are in. Empty when it isn't known, such as after joining any public arena.
+    pub arena_name: String,
+    // Last arena list received from the server.
+    pub arena_list: Vec<ArenaDirectoryEntry>,
+    pub ship: Ship,
+    // Sent to the server in the password packet. Redirects hand us a new one for the target server.
+    pub login_id: u32,
 
     pub registration: RegistrationFormMessage,
+
+    pub redirect_count: u32,
+    pub last_redirect_tick: LocalTick,
+
+    events: VecDeque<BotEvent>,
 }
 
 impl Client {
@@ -46,9 +105,10 @@ impl Client {
         zone: &str,
         remote_ip: &str,
         remote_port: u16,
+        encryption_version: EncryptionClientVersion,
         registration: RegistrationFormMessage,
     ) -> anyhow::Result<Client> {
-        let connection = Connection::new(remote_ip, remote_port)?;
+        let connection = Connection::new(remote_ip, remote_port, encryption_version)?;
 
         Ok(Client {
             connection,
@@ -56,75 +116,758 @@ impl Client {
             settings: None,
             last_position_tick: LocalTick::now(),
             player_manager: PlayerManager::new(),
+            player_id: PlayerId::invalid(),
+            flag_manager: FlagManager::new(),
+            ball_manager: BallManager::new(),
+            koth: Koth::new(),
+            brick_manager: BrickManager::new(),
+            doors: Doors::new(),
+            weapon_manager: WeaponManager::new(),
+            chat_queue: ChatQueue::new(),
+            chat_logger: None,
+            observer: Observer::new(OBSERVER_ROTATE_TICKS),
+            extra_position_info: false,
+            ship_state: ShipState::new(),
+            last_simulation_tick: LocalTick::now(),
+            respawn_tick: None,
+            movement_goal: None,
             username: username.to_owned(),
             password: password.to_owned(),
             zone: zone.to_owned(),
+            arena_request: ArenaRequest::AnyPublic,
+            arena_name: String::new(),
+            arena_list: Vec::new(),
+            ship: Ship::Spectator,
+            login_id: 123412,
             registration,
+            redirect_count: 0,
+            last_redirect_tick: LocalTick::now(),
+            events: VecDeque::new(),
         })
     }
 
-    pub fn run(&mut self, rx: std::sync::mpsc::Receiver<()>) -> anyhow::Result<()> {
+    // Runs the client until it disconnects or a control-c signal is received.
+    // Each event is passed to the handler after the client has applied it to its state.
+    pub fn run<F>(
+        &mut self,
+        rx: std::sync::mpsc::Receiver<()>,
+        mut handler: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(&mut Client, BotEvent) -> anyhow::Result<()>,
+    {
         loop {
             // Exit loop if we receive a control-c signal.
             if let Ok(_) = rx.try_recv() {
                 break;
             }
 
-            let now = LocalTick::now();
+            let connected = self.tick()?;
 
-            loop {
-                let message = self.connection.tick();
-                if let Err(e) = message {
-                    println!("Error: {}", e);
-                    if e.is::<std::io::Error>() {
-                        break;
-                    }
-                    continue;
-                }
+            while let Some(event) = self.poll_event() {
+                handler(self, event)?;
+            }
+
+            if !connected {
+                break;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
 
-                let message = message.unwrap();
+        // Always send disconnect when we are exiting so we don't linger on the server.
+        let disconnect = DisconnectMessage {};
+        self.connection.send(&disconnect)?;
+
+        Ok(())
+    }
 
-                if let Some(message) = message {
-                    self.process_message(message)?;
-                } else {
-                    // We are done processing everything now.
+    // Processes all pending network messages and sends anything that is due.
+    // Returns false once the connection has been disconnected.
+    pub fn tick(&mut self) -> anyhow::Result<bool> {
+        let now = LocalTick::now();
+
+        loop {
+            let message = self.connection.tick();
+            if let Err(e) = message {
+                println!("Error: {}", e);
+                if e.is::<std::io::Error>() {
                     break;
                 }
+                continue;
             }
 
-            match self.connection.state {
-                ConnectionState::Playing => {
-                    if now.diff(&self.last_position_tick) > 300 {
-                        let position = PositionMessage {
-                            direction: 0,
-                            timestamp: self.connection.get_server_tick(),
-                            x_position: 0,
-                            y_position: 0,
-                            x_velocity: 0,
-                            y_velocity: 0,
-                            togglables: 0,
-                            bounty: 0,
-                            energy: 0,
-                            weapon_info: WeaponData::new(0),
-                        };
-
-                        self.connection.send(&position)?;
-
-                        self.last_position_tick = now;
+            let message = message.unwrap();
+
+            if let Some(message) = message {
+                self.process_message(message)?;
+            } else {
+                // We are done processing everything now.
+                break;
+            }
+        }
+
+        match self.connection.state {
+            ConnectionState::Playing => {
+                let connection = &mut self.connection;
+                self.chat_queue
+                    .send_ready(now, |message| connection.send_reliable(message))?;
+
+                if let Some(settings) = &self.settings {
+                    let server_tick = self.connection.get_server_tick();
+
+                    self.brick_manager
+                        .expire(server_tick, settings.brick_time as i32);
+
+                    // Weapons are checked against where players should be now rather than their last update.
+                    // Predicting is costly, so it's skipped while nothing is in flight.
+                    let targets: Vec<Target> = if self.weapon_manager.projectiles.is_empty() {
+                        Vec::new()
+                    } else {
+                        self.player_manager
+                            .players
+                            .values()
+                            .filter(|player| player.ship != Ship::Spectator)
+                            .map(|player| Target {
+                                frequency: player.frequency,
+                                position: self
+                                    .predict_player(&player.id, server_tick)
+                                    .map(|sample| sample.position)
+                                    .unwrap_or(player.position),
+                                radius: settings
+                                    .ship(player.ship)
+                                    .map(|ship| ship.radius)
+                                    .filter(|&radius| radius > 0)
+                                    .unwrap_or(DEFAULT_SHIP_RADIUS),
+                            })
+                            .collect()
+                    };
+
+                    let map = &self.map;
+                    let doors = &self.doors;
+                    let bricks = &self.brick_manager;
+
+                    let explosions = self.weapon_manager.update(
+                        server_tick,
+                        settings,
+                        &targets,
+                        |x, y, frequency| {
+                            map.is_solid_at(x, y, doors, server_tick)
+                                || bricks.is_blocking(x, y, frequency)
+                        },
+                    );
+
+                    for explosion in explosions {
+                        self.events.push_back(BotEvent::Explosion(explosion));
                     }
                 }
-                ConnectionState::Disconnected => {
-                    break;
+
+                if self.koth.update(now, self.player_id) {
+                    let koth_end = KothEndMessage {};
+                    self.connection.send_reliable(&koth_end)?;
+
+                    self.events.push_back(BotEvent::KothExpired);
                 }
-                _ => {}
+
+                if self.ship == Ship::Spectator
+                    && let Some(target) = self.observer.update(now, &self.player_manager)
+                {
+                    let spectate = SpectateMessage { player_id: target };
+                    self.connection.send_reliable(&spectate)?;
+                }
+
+                self.simulate_ship(now);
+
+                if now.diff(&self.last_position_tick) > self.position_delay() {
+                    self.send_position(WeaponData::new(0))?;
+                }
+            }
+            ConnectionState::Disconnected => {
+                return Ok(false);
             }
+            _ => {}
+        }
 
-            std::thread::sleep(std::time::Duration::from_millis(1));
+        Ok(true)
+    }
+
+    pub fn poll_event(&mut self) -> Option<BotEvent> {
+        self.events.pop_front()
+    }
+
+    // Chat is queued and sent from tick so it stays within the rate limits.
+    pub fn send_chat(&mut self, message: &SendChatMessage) -> anyhow::Result<()> {
+        self.chat_queue.push(message);
+        Ok(())
+    }
+
+    // Frequency messages are addressed to a player, so this fails if nobody is on the frequency.
+    pub fn send_frequency_chat(&mut self, frequency: u16, text: &str) -> anyhow::Result<()> {
+        let target = self
+            .player_manager
+            .get_by_frequency(frequency)
+            .ok_or_else(|| anyhow::anyhow!("no players on frequency {}", frequency))?;
+
+        let message = SendChatMessage::frequency(target.id, text);
+        self.send_chat(&message)
+    }
+
+    // Estimated current position of a ball, using the arena's powerball settings.
+    pub fn ball_position(&self, ball_id: u8) -> Option<Position> {
+        let settings = self.settings.as_ref()?;
+        let now = self.connection.get_server_tick();
+
+        self.ball_manager
+            .extrapolate(ball_id, now, settings, &self.map, &self.player_manager)
+    }
+
+    // Whether a tile currently blocks a ship on the given frequency, including doors and live bricks.
+    pub fn is_solid(&self, x: u16, y: u16, frequency: u16) -> bool {
+        let tick = self.connection.get_server_tick();
+
+        self.map.is_solid_at(x, y, &self.doors, tick)
+            || self.brick_manager.is_blocking(x, y, frequency)
+    }
+
+    // Where the player is estimated to be now, from their last update and velocity.
+    pub fn player_position(&self, player_id: &PlayerId) -> Option<Position> {
+        let tick = self.connection.get_server_tick();
+        self.predict_player(player_id, tick)
+            .map(|sample| sample.position)
+    }
+
+    // Position of the player at an earlier tick, between two of their updates.
+    pub fn interpolated_position(
+        &self,
+        player_id: &PlayerId,
+        tick: ServerTick,
+    ) -> Option<Position> {
+        let player = self.player_manager.get(player_id)?;
+
+        player.history.interpolate(tick).or_else(|| {
+            self.predict_player(player_id, tick)
+                .map(|sample| sample.position)
+        })
+    }
+
+    // Advances the player's last update to the tick. Players that have never sent a position aren't predicted.
+    pub fn predict_player(&self, player_id: &PlayerId, tick: ServerTick) -> Option<PositionSample> {
+        let player = self.player_manager.get(player_id)?;
+
+        player.history.latest()?;
+
+        let (radius, bounce_factor) = match &self.settings {
+            Some(settings) => (
+                settings
+                    .ship(player.ship)
+                    .map(|ship| ship.radius)
+                    .unwrap_or(0),
+                settings.bounce_factor,
+            ),
+            None => (0, BOUNCE_FACTOR_BASE as i16),
+        };
+
+        let radius = if radius == 0 {
+            DEFAULT_SHIP_RADIUS
+        } else {
+            radius
+        };
+
+        let sample = PositionSample {
+            timestamp: player.last_position_timestamp,
+            position: player.position,
+            velocity: player.velocity,
+        };
+
+        Some(prediction::predict(
+            &self.map,
+            &sample,
+            tick,
+            radius,
+            bounce_factor,
+        ))
+    }
+
+    // Applies a position update if it is newer than the last one. The prediction made for the update's
+    // timestamp is compared against it to keep the player's error statistics.
+    fn update_player_position(
+        &mut self,
+        player_id: &PlayerId,
+        timestamp: ServerTick,
+        position: Position,
+        velocity: Velocity,
+        direction: u8,
+    ) -> bool {
+        let predicted = self
+            .player_manager
+            .get(player_id)
+            .filter(|player| {
+                timestamp.diff(&player.last_position_timestamp) <= MAX_PREDICTION_TICKS
+            })
+            .and_then(|_| self.predict_player(player_id, timestamp))
+            .map(|sample| sample.position);
+
+        let Some(player) = self.player_manager.get_mut(player_id) else {
+            return false;
+        };
+
+        if player.last_position_timestamp >= timestamp {
+            return false;
         }
 
-        // Always send disconnect when we are exiting so we don't linger on the server.
-        let disconnect = DisconnectMessage {};
-        self.connection.send(&disconnect)?;
+        player.position = position;
+        player.velocity = velocity;
+        player.direction = direction;
+        player.last_position_timestamp = timestamp;
+
+        let sample = PositionSample {
+            timestamp,
+            position,
+            velocity,
+        };
+        player.history.push(sample, predicted);
+
+        self.events.push_back(BotEvent::PositionUpdated(player.id));
+
+        true
+    }
+
+    // Spectators only need to keep the server aware of them. Ships send as often as the arena asks.
+    fn position_delay(&self) -> i32 {
+        match &self.settings {
+            Some(settings) if self.ship != Ship::Spectator && self.respawn_tick.is_none() => {
+                settings.send_position_delay.max(1) as i32
+            }
+            _ => 300,
+        }
+    }
+
+    // Sends our current position right away, along with any weapon that is being fired.
+    pub fn send_position(&mut self, weapon_info: WeaponData) -> anyhow::Result<()> {
+        let timestamp = self.connection.get_server_tick();
+
+        let position = if self.ship == Ship::Spectator {
+            PositionMessage {
+                direction: 0,
+                timestamp,
+                x_position: 0,
+                y_position: 0,
+                x_velocity: 0,
+                y_velocity: 0,
+                togglables: 0,
+                bounty: 0,
+                energy: 0,
+                weapon_info,
+                extra: None,
+            }
+        } else {
+            let mut position = self.ship_state.position_message(timestamp, weapon_info);
+            if self.extra_position_info {
+                position.extra = Some(self.ship_state.extra_position_data());
+            }
+            position
+        };
+
+        self.connection.send(&position)?;
+        self.last_position_tick = LocalTick::now();
+
+        // The warp flash is only shown once.
+        self.ship_state.status &= !StatusFlags::Flash;
+
+        Ok(())
+    }
+
+    // Replaces all of the ship's input at once.
+    pub fn set_input(&mut self, input: ShipInput) {
+        self.movement_goal = None;
+        self.ship_state.input = input;
+    }
+
+    pub fn set_thrust(&mut self, thrust: bool) {
+        self.movement_goal = None;
+        self.ship_state.input.thrust = thrust;
+    }
+
+    pub fn set_reverse(&mut self, reverse: bool) {
+        self.movement_goal = None;
+        self.ship_state.input.reverse = reverse;
+    }
+
+    pub fn set_turn(&mut self, turn: Turn) {
+        self.movement_goal = None;
+        self.ship_state.input.left = turn == Turn::Left;
+        self.ship_state.input.right = turn == Turn::Right;
+    }
+
+    pub fn set_afterburner(&mut self, afterburner: bool) {
+        self.movement_goal = None;
+        self.ship_state.input.afterburner = afterburner;
+    }
+
+    // Turns to face the direction, which is in the 0 to 39 units used by position packets.
+    pub fn rotate_to(&mut self, direction: u8) {
+        let rotation = (direction as i32 % 40) * (flight::ROTATION_PER_TURN / 40);
+        self.movement_goal = Some(MovementGoal::Rotate(rotation));
+    }
+
+    // Turns to face a point on the map, in pixels.
+    pub fn face(&mut self, x: u32, y: u32) {
+        let dx = x as f32 - self.ship_state.pixel_x() as f32;
+        let dy = y as f32 - self.ship_state.pixel_y() as f32;
+
+        let rotation = flight::rotation_from_vector(dx, dy);
+        self.movement_goal = Some(MovementGoal::Rotate(rotation));
+    }
+
+    // Flies to the pixel position and stops there.
+    pub fn move_to(&mut self, x: u32, y: u32) {
+        self.movement_goal = Some(MovementGoal::MoveTo(x, y));
+    }
+
+    // Flies to the middle of the tile and stops there.
+    pub fn move_to_tile(&mut self, x: u16, y: u16) {
+        self.move_to(x as u32 * 16 + 8, y as u32 * 16 + 8);
+    }
+
+    // Brakes until the ship is no longer moving.
+    pub fn stop(&mut self) {
+        self.movement_goal = Some(MovementGoal::Stop);
+    }
+
+    // Whether the ship is still working toward a goal from rotate_to, face, move_to or stop.
+    pub fn is_moving_to_goal(&self) -> bool {
+        self.movement_goal.is_some()
+    }
+
+    pub fn fire_gun(&mut self) -> anyhow::Result<bool> {
+        self.fire(FireKind::Gun)
+    }
+
+    pub fn fire_multifire(&mut self) -> anyhow::Result<bool> {
+        self.fire(FireKind::Multifire)
+    }
+
+    pub fn fire_bomb(&mut self) -> anyhow::Result<bool> {
+        self.fire(FireKind::Bomb)
+    }
+
+    pub fn fire_mine(&mut self) -> anyhow::Result<bool> {
+        self.fire(FireKind::Mine)
+    }
+
+    // Sends the weapon with a position packet if the ship can fire it right now.
+    // Returns false when it can't, such as while waiting on the fire delay, low on energy or in a safe zone.
+    pub fn fire(&mut self, kind: FireKind) -> anyhow::Result<bool> {
+        let Some(weapon) = self.prepare_fire(kind) else {
+            return Ok(false);
+        };
+
+        self.send_position(weapon)?;
+
+        if kind == FireKind::Mine
+            && let Some(settings) = &self.settings
+        {
+            let position = Position::new(self.ship_state.pixel_x(), self.ship_state.pixel_y());
+            self.weapon_manager.add_mine(
+                self.player_id,
+                self.frequency(),
+                weapon,
+                position,
+                self.connection.get_server_tick(),
+                settings,
+            );
+        }
+
+        Ok(true)
+    }
+
+    fn prepare_fire(&mut self, kind: FireKind) -> Option<WeaponData> {
+        if !self.in_game() {
+            return None;
+        }
+
+        let settings = self.settings.as_ref()?;
+        let ship_settings = settings.ship(self.ship)?;
+
+        let tile_x = (self.ship_state.pixel_x() / 16).min(1023) as u16;
+        let tile_y = (self.ship_state.pixel_y() / 16).min(1023) as u16;
+        if self.map.get_tile(tile_x, tile_y) == TILE_ID_SAFE {
+            return None;
+        }
+
+        let flagger = self
+            .player_manager
+            .get(&self.player_id)
+            .is_some_and(|player| player.flag_count > 0);
+
+        // A team limit of zero means only the per ship limit applies.
+        if kind == FireKind::Mine {
+            let mines = self.weapon_manager.mine_count(self.player_id);
+            let team_mines = self.weapon_manager.team_mine_count(self.frequency());
+
+            if mines >= ship_settings.max_mines as usize
+                || (settings.team_max_mines > 0 && team_mines >= settings.team_max_mines as usize)
+            {
+                return None;
+            }
+        }
+
+        self.ship_state.fire(kind, ship_settings, settings, flagger)
+    }
+
+    pub fn use_repel(&mut self) -> anyhow::Result<bool> {
+        self.use_weapon_item(WeaponKind::Repel)
+    }
+
+    pub fn use_burst(&mut self) -> anyhow::Result<bool> {
+        self.use_weapon_item(WeaponKind::Burst)
+    }
+
+    pub fn use_decoy(&mut self) -> anyhow::Result<bool> {
+        self.use_weapon_item(WeaponKind::Decoy)
+    }
+
+    pub fn use_thor(&mut self) -> anyhow::Result<bool> {
+        self.use_weapon_item(WeaponKind::Thor)
+    }
+
+    fn use_weapon_item(&mut self, kind: WeaponKind) -> anyhow::Result<bool> {
+        if !self.in_game() {
+            return Ok(false);
+        }
+
+        let Some(settings) = &self.settings else {
+            return Ok(false);
+        };
+
+        let Some(ship_settings) = settings.ship(self.ship) else {
+            return Ok(false);
+        };
+
+        let Some(weapon) = self
+            .ship_state
+            .use_weapon_item(kind, ship_settings, settings)
+        else {
+            return Ok(false);
+        };
+
+        self.send_position(weapon)?;
+
+        Ok(true)
+    }
+
+    // Rockets aren't sent to the server. Other players see them through our position updates.
+    pub fn use_rocket(&mut self) -> bool {
+        if !self.in_game() {
+            return false;
+        }
+
+        let Some(ship_settings) = self
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.ship(self.ship))
+        else {
+            return false;
+        };
+
+        self.ship_state.use_rocket(ship_settings)
+    }
+
+    // Drops a brick on the tile we are on. The server places it and tells everyone with BrickDrop.
+    pub fn drop_brick(&mut self) -> anyhow::Result<bool> {
+        if !self.in_game() || !self.ship_state.use_brick() {
+            return Ok(false);
+        }
+
+        let brick = DropBrickMessage {
+            x: (self.ship_state.pixel_x() / 16) as u16,
+            y: (self.ship_state.pixel_y() / 16) as u16,
+        };
+        self.connection.send_reliable(&brick)?;
+
+        Ok(true)
+    }
+
+    pub fn place_portal(&mut self) -> bool {
+        if !self.in_game() {
+            return false;
+        }
+
+        let Some(settings) = &self.settings else {
+            return false;
+        };
+
+        self.ship_state.place_portal(settings)
+    }
+
+    // Warps to our portal if one is placed, otherwise back to our spawn.
+    pub fn warp(&mut self) -> anyhow::Result<bool> {
+        if !self.in_game() {
+            return Ok(false);
+        }
+
+        let Some(settings) = &self.settings else {
+            return Ok(false);
+        };
+
+        let (x, y) = self.spawn_position(settings);
+        if !self.ship_state.warp(x, y) {
+            return Ok(false);
+        }
+
+        self.movement_goal = None;
+        self.send_position(WeaponData::new(0))?;
+
+        Ok(true)
+    }
+
+    // Whether we are in a ship and alive, so the ship can act.
+    fn in_game(&self) -> bool {
+        self.ship != Ship::Spectator && self.respawn_tick.is_none()
+    }
+
+    // Our own frequency, or zero before the server has told us about ourselves.
+    fn frequency(&self) -> u16 {
+        self.player_manager
+            .get(&self.player_id)
+            .map(|player| player.frequency)
+            .unwrap_or(0)
+    }
+
+    // Prizes that were given to us, whether picked up or granted by the server.
+    fn apply_prize(&mut self, prize_id: i16) -> anyhow::Result<()> {
+        let Some(prize) = Prize::from_id(prize_id.unsigned_abs() as u8) else {
+            return Ok(());
+        };
+
+        let Some(settings) = &self.settings else {
+            return Ok(());
+        };
+
+        if prize == Prize::Warp && prize_id > 0 {
+            let (x, y) = self.spawn_position(settings);
+
+            self.ship_state.set_position(x, y);
+            self.ship_state.status |= StatusFlags::Flash;
+            self.movement_goal = None;
+            return self.send_position(WeaponData::new(0));
+        }
+
+        let Some(ship_settings) = settings.ship(self.ship) else {
+            return Ok(());
+        };
+
+        self.ship_state
+            .apply_prize(prize, prize_id < 0, ship_settings);
+
+        Ok(())
+    }
+
+    // Runs our ship's movement for every tick since the last call. Nothing moves while spectating or dead.
+    fn simulate_ship(&mut self, now: LocalTick) {
+        let ticks = now
+            .diff(&self.last_simulation_tick)
+            .clamp(0, MAX_SIMULATION_TICKS);
+        self.last_simulation_tick = now;
+
+        if let Some(respawn_tick) = self.respawn_tick
+            && now >= respawn_tick
+        {
+            self.respawn_tick = None;
+            self.spawn_ship();
+        }
+
+        if self.ship == Ship::Spectator || self.respawn_tick.is_some() {
+            return;
+        }
+
+        let Some(settings) = &self.settings else {
+            return;
+        };
+
+        let Some(ship_settings) = settings.ship(self.ship) else {
+            return;
+        };
+
+        let frequency = self.frequency();
+        let server_tick = self.connection.get_server_tick();
+
+        for _ in 0..ticks {
+            if let Some(goal) = self.movement_goal
+                && self.ship_state.steer(goal)
+            {
+                self.movement_goal = None;
+            }
+
+            let map = &self.map;
+            let doors = &self.doors;
+            let bricks = &self.brick_manager;
+
+            self.ship_state
+                .update(ship_settings, settings, map, |x, y| {
+                    map.is_solid_at(x, y, doors, server_tick) || bricks.is_blocking(x, y, frequency)
+                });
+        }
+    }
+
+    // Places our ship at our team's spawn point with its initial stats.
+    fn spawn_ship(&mut self) {
+        let Some(settings) = &self.settings else {
+            return;
+        };
+
+        let Some(ship_settings) = settings.ship(self.ship) else {
+            return;
+        };
+
+        let (x, y) = self.spawn_position(settings);
+
+        self.ship_state.spawn(ship_settings, x, y);
+        self.movement_goal = None;
+    }
+
+    // Middle of our team's spawn tile in pixels. A spawn point of zero means the middle of the map.
+    fn spawn_position(&self, settings: &ArenaSettings) -> (u32, u32) {
+        let frequency = self.frequency();
+
+        let spawn = &settings.spawn_settings[frequency as usize % settings.spawn_settings.len()];
+        let (tile_x, tile_y) = if spawn.x == 0 && spawn.y == 0 {
+            (512, 512)
+        } else {
+            (spawn.x as u32, spawn.y as u32)
+        };
+
+        (tile_x * 16 + 8, tile_y * 16 + 8)
+    }
+
+    // The server responds with the arena list, which is reported as a BotEvent::ArenaList.
+    pub fn request_arena_list(&mut self) -> anyhow::Result<()> {
+        let chat = SendChatMessage::public("?arena");
+        self.send_chat(&chat)
+    }
+
+    // Leaves the current arena and joins another one. Anything tied to the old arena is cleared.
+    pub fn join_arena(&mut self, arena_request: ArenaRequest) -> anyhow::Result<()> {
+        if matches!(
+            self.connection.state,
+            ConnectionState::MapDownload | ConnectionState::Playing
+        ) {
+            let leave = LeaveArenaMessage {};
+            self.connection.send_reliable(&leave)?;
+        }
+
+        self.arena_request = arena_request;
+        self.arena_name = arena_request.arena_name().unwrap_or_default();
+
+        self.reset_arena_state();
+
+        self.connection.state = ConnectionState::ArenaLogin;
+
+        let join = ArenaJoinMessage::new(self.ship, 1920, 1080, arena_request);
+        self.connection.send_reliable(&join)?;
+
+        self.events
+            .push_back(BotEvent::ArenaChanged { arena_request });
 
         Ok(())
     }
@@ -139,7 +882,7 @@ impl Client {
                     0x1231241,
                     240,
                     0x86,
-                    123412,
+                    self.login_id,
                 );
 
                 self.connection.send_reliable(&password)?;
@@ -155,50 +898,57 @@ impl Client {
 
     fn process_game_message(&mut self, message: &GameServerMessage) -> anyhow::Result<()> {
         match message {
-            GameServerMessage::Chat(chat) => match chat.kind {
-                ChatKind::Public | ChatKind::PublicMacro => {
-                    if let Some(sender) = self.player_manager.get(&chat.sender) {
-                        println!("{}> {}", sender.name, chat.message);
-                    }
-                }
-                ChatKind::Team => {
-                    if let Some(sender) = self.player_manager.get(&chat.sender) {
-                        println!("T {}> {}", sender.name, chat.message);
-                    }
-                }
-                ChatKind::Frequency => {
-                    if let Some(sender) = self.player_manager.get(&chat.sender) {
-                        println!("F {}> {}", sender.name, chat.message);
-                    }
-                }
-                ChatKind::Arena | ChatKind::Error | ChatKind::Warning => {
-                    if !chat.message.is_empty() {
-                        println!("A {}", chat.message);
-                    }
-                }
-                ChatKind::Private => {
-                    if let Some(sender) = self.player_manager.get(&chat.sender) {
-                        println!("P {}> {}", sender.name, chat.message);
-                    }
-                }
-                ChatKind::RemotePrivate => {
-                    println!("RP {}", chat.message);
+            GameServerMessage::Chat(chat) => {
+                if let Some(logger) = &mut self.chat_logger
+                    && let Err(e) = logger.log_message(&self.arena_name, chat, &self.player_manager)
+                {
+                    println!("Error writing chat log: {}", e);
                 }
-                ChatKind::Channel => {
-                    println!("C {}", chat.message);
+
+                // Remote, squad and channel messages include the sender in the text.
+                let text = parse_chat_text(chat.kind, &chat.message);
+
+                let sender_name = match chat.kind {
+                    ChatKind::Public
+                    | ChatKind::PublicMacro
+                    | ChatKind::Team
+                    | ChatKind::Frequency
+                    | ChatKind::Private => self
+                        .player_manager
+                        .get(&chat.sender)
+                        .map(|sender| sender.name.clone()),
+                    // Arena messages have no sender.
+                    _ => text.sender_name.map(|name| name.to_owned()),
+                };
+
+                let is_arena = matches!(
+                    chat.kind,
+                    ChatKind::Arena | ChatKind::Error | ChatKind::Warning
+                );
+
+                if !is_arena || !chat.message.is_empty() {
+                    self.events.push_back(BotEvent::Chat {
+                        kind: chat.kind,
+                        sender: chat.sender,
+                        sender_name,
+                        squad: text.squad.map(|squad| squad.to_owned()),
+                        channel: text.channel,
+                        message: text.body.to_owned(),
+                    });
                 }
-            },
+            }
             GameServerMessage::PasswordResponse(password_response) => {
-                println!("Got password response: {}", password_response.response);
+                self.events
+                    .push_back(BotEvent::LoginResult(password_response.response.clone()));
 
                 match &password_response.response {
                     LoginResponse::Ok => {
-                        let arena_request = ArenaJoinMessage::new(
-                            Ship::Spectator,
-                            1920,
-                            1080,
-                            ArenaRequest::AnyPublic,
-                        );
+                        self.arena_name = self.arena_request.arena_name().unwrap_or_default();
+
+                        self.connection.state = ConnectionState::ArenaLogin;
+
+                        let arena_request =
+                            ArenaJoinMessage::new(self.ship, 1920, 1080, self.arena_request);
                         self.connection.send_reliable(&arena_request)?;
                     }
                     LoginResponse::Unregistered => {
@@ -217,7 +967,7 @@ impl Client {
                                 0x1231241,
                                 240,
                                 0x86,
-                                123412,
+                                self.login_id,
                             );
 
                             self.connection.send_reliable(&password)?;
@@ -233,8 +983,12 @@ impl Client {
                 println!("Received arena settings");
                 // println!("{:?}", settings);
                 self.settings = Some(settings_message.clone());
+                self.doors
+                    .set_mode(settings_message.door_mode, settings_message.door_delay);
             }
             GameServerMessage::SynchronizationRequest(sync) => {
+                self.doors.seed(sync.door_seed, sync.timestamp);
+
                 if sync.checksum_key != 0 && self.map.checksum != 0 {
                     // Send security packet
                     println!("Sync requested");
@@ -257,9 +1011,6 @@ impl Client {
                 }
             }
             GameServerMessage::PlayerEntering(entering) => {
-                // TODO: Remove. Just here for testing so we get position packets from anywhere.
-                let mut sent_spectate_request = false;
-
                 for entry in &entering.players {
                     let mut player = Player::new(
                         entry.player_id,
@@ -270,156 +1021,251 @@ impl Client {
                     );
 
                     player.flag_count = entry.flag_count;
+
+                    if entry.has_koth {
+                        self.koth.crowns.insert(entry.player_id);
+                    }
+                    player.kill_points = entry.kill_points;
+                    player.flag_points = entry.flag_points;
+                    player.wins = entry.kills;
+                    player.losses = entry.deaths;
                     player.attach_parent = entry.attach_parent;
                     player.last_position_timestamp = self.connection.get_server_tick();
 
                     // If there was someone already in this place, say that they left.
                     // This can happen when joining at the same exact time as other players.
                     if let Some(old_player) = self.player_manager.add_player(player) {
-                        println!("{} left arena", old_player.name);
+                        self.events.push_back(BotEvent::PlayerLeft {
+                            player_id: old_player.id,
+                            name: old_player.name,
+                        });
                     }
 
-                    println!("{} entered arena {:?}", entry.name, entry.ship);
+                    self.events
+                        .push_back(BotEvent::PlayerEntered(entry.player_id));
 
-                    if !sent_spectate_request && entry.ship != Ship::Spectator {
-                        let spectate_request = SpectateMessage {
-                            player_id: entry.player_id,
-                        };
-
-                        self.connection.send_reliable(&spectate_request)?;
-                        sent_spectate_request = true;
+                    if entry.player_id == self.player_id {
+                        self.ship = entry.ship;
+                        self.spawn_ship();
                     }
                 }
             }
             GameServerMessage::PlayerLeaving(leaving) => {
+                self.observer.remove_target(leaving.player_id);
+                self.flag_manager.on_player_leave(leaving.player_id);
+                self.koth.remove_player(leaving.player_id);
+                self.ball_manager.on_player_leave(leaving.player_id);
+                self.weapon_manager.on_player_leave(leaving.player_id);
+
                 if let Some(player) = self.player_manager.remove_player(&leaving.player_id) {
-                    println!("{} left arena", player.name);
+                    self.events.push_back(BotEvent::PlayerLeft {
+                        player_id: player.id,
+                        name: player.name,
+                    });
                 }
             }
             GameServerMessage::SmallPosition(message) => {
-                if let Some(player) = self.player_manager.get_mut(&message.player_id) {
-                    let message_timestamp =
-                        ServerTick::from_mini(self.connection.get_server_tick(), message.timestamp)
-                            - message.ping as i32;
-
-                    if player.last_position_timestamp < message_timestamp {
-                        player.position = Position::new(message.x as u32, message.y as u32);
-                        player.velocity =
-                            Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
-                        player.direction = message.direction;
-                        player.bounty = message.bounty as u16;
-                        player.status = message.status;
-                        player.ping = message.ping;
-                        player.last_position_timestamp = message_timestamp;
-
-                        println!(
-                            "{} at {:?} {:?}",
-                            player.name, player.position, player.velocity
-                        );
-                    }
+                let timestamp =
+                    ServerTick::from_mini(self.connection.get_server_tick(), message.timestamp)
+                        - message.ping as i32;
+                let position = Position::new(message.x as u32, message.y as u32);
+                let velocity = Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
+
+                if self.update_player_position(
+                    &message.player_id,
+                    timestamp,
+                    position,
+                    velocity,
+                    message.direction,
+                ) && let Some(player) = self.player_manager.get_mut(&message.player_id)
+                {
+                    player.bounty = message.bounty as u16;
+                    player.stats.record_bounty(timestamp, player.bounty);
+                    player.status = message.status;
+                    player.ping = message.ping;
                 }
+
+                self.player_manager.move_turrets(&message.player_id);
             }
             GameServerMessage::LargePosition(message) => {
-                if let Some(player) = self.player_manager.get_mut(&message.player_id) {
-                    let message_timestamp =
-                        ServerTick::from_mini(self.connection.get_server_tick(), message.timestamp)
-                            - message.ping as i32;
-
-                    if player.last_position_timestamp < message_timestamp {
-                        player.position = Position::new(message.x as u32, message.y as u32);
-                        player.velocity =
-                            Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
-                        player.direction = message.direction;
-                        player.bounty = message.bounty;
-                        player.status = message.status;
-                        player.ping = message.ping;
-                        player.last_position_timestamp = message_timestamp;
-
-                        println!(
-                            "{} at {:?} {}",
-                            player.name, player.position, message.weapon
-                        );
+                let timestamp =
+                    ServerTick::from_mini(self.connection.get_server_tick(), message.timestamp)
+                        - message.ping as i32;
+                let position = Position::new(message.x as u32, message.y as u32);
+                let velocity = Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
+
+                if self.update_player_position(
+                    &message.player_id,
+                    timestamp,
+                    position,
+                    velocity,
+                    message.direction,
+                ) && let Some(player) = self.player_manager.get_mut(&message.player_id)
+                {
+                    player.bounty = message.bounty;
+                    player.stats.record_bounty(timestamp, player.bounty);
+                    player.status = message.status;
+                    player.ping = message.ping;
+
+                    if let Some(extra) = &message.extra {
+                        player.energy = Some(extra.energy);
+                        player.items = Some(extra.items);
                     }
                 }
+
+                if message.weapon.kind() != WeaponKind::None
+                    && message.player_id != self.player_id
+                    && let Some(player) = self.player_manager.get(&message.player_id)
+                    && let Some(settings) = &self.settings
+                {
+                    self.weapon_manager.on_fire(
+                        message,
+                        player.ship,
+                        player.frequency,
+                        timestamp,
+                        settings,
+                    );
+
+                    self.events.push_back(BotEvent::WeaponFired {
+                        player_id: message.player_id,
+                        weapon: message.weapon,
+                    });
+                }
+
+                self.player_manager.move_turrets(&message.player_id);
             }
             GameServerMessage::BatchedSmallPosition(message) => {
                 for message in &message.positions {
-                    if let Some(player) = self.player_manager.get_mut(&message.player_id) {
-                        let message_timestamp = ServerTick::from_batched(
-                            self.connection.get_server_tick(),
-                            message.timestamp,
-                        );
+                    let timestamp = ServerTick::from_batched(
+                        self.connection.get_server_tick(),
+                        message.timestamp,
+                    );
+                    let position = Position::new(message.x as u32, message.y as u32);
+                    let velocity =
+                        Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
 
-                        if player.last_position_timestamp < message_timestamp {
-                            player.position = Position::new(message.x as u32, message.y as u32);
-                            player.velocity =
-                                Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
-                            player.direction = message.direction;
-                            player.last_position_timestamp = message_timestamp;
+                    self.update_player_position(
+                        &message.player_id,
+                        timestamp,
+                        position,
+                        velocity,
+                        message.direction,
+                    );
 
-                            println!(
-                                "{} at sbatched {:?} {:?}",
-                                player.name, player.position, player.velocity
-                            );
-                        }
-                    }
+                    self.player_manager.move_turrets(&message.player_id);
                 }
             }
             GameServerMessage::BatchedLargePosition(message) => {
                 for message in &message.positions {
-                    if let Some(player) = self.player_manager.get_mut(&message.player_id) {
-                        let message_timestamp = ServerTick::from_batched(
-                            self.connection.get_server_tick(),
-                            message.timestamp,
-                        );
-
-                        if player.last_position_timestamp < message_timestamp {
-                            player.position = Position::new(message.x as u32, message.y as u32);
-                            player.velocity =
-                                Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
-                            player.direction = message.direction;
-                            player.last_position_timestamp = message_timestamp;
-                            if let Some(status) = message.status {
-                                player.status = status;
-                            }
+                    let timestamp = ServerTick::from_batched(
+                        self.connection.get_server_tick(),
+                        message.timestamp,
+                    );
+                    let position = Position::new(message.x as u32, message.y as u32);
+                    let velocity =
+                        Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
 
-                            println!(
-                                "{} at lbatched {:?} {:?}",
-                                player.name, player.position, player.velocity
-                            );
-                        }
+                    if self.update_player_position(
+                        &message.player_id,
+                        timestamp,
+                        position,
+                        velocity,
+                        message.direction,
+                    ) && let Some(status) = message.status
+                        && let Some(player) = self.player_manager.get_mut(&message.player_id)
+                    {
+                        player.status = status;
                     }
+
+                    self.player_manager.move_turrets(&message.player_id);
                 }
             }
             GameServerMessage::PlayerDeath(message) => {
-                if let Some(killer) = self.player_manager.get(&message.killer_id) {
-                    if let Some(killed) = self.player_manager.get(&message.killed_id) {
-                        println!("{} killed by {}", killed.name, killer.name);
-                    }
+                // Flag carriers are given extra points for kills.
+                let mut points = message.bounty as u32;
+                if let Some(killer) = self.player_manager.get(&message.killer_id)
+                    && killer.flag_count > 0
+                    && let Some(settings) = &self.settings
+                {
+                    points *= (settings.flagger_kill_multiplier.max(0) + 1) as u32;
                 }
 
-                if let Some(killer) = self.player_manager.get_mut(&message.killer_id) {
-                    killer.flag_count += message.flag_transfer;
+                self.player_manager.on_death(
+                    &message.killer_id,
+                    &message.killed_id,
+                    points,
+                    self.connection.get_server_tick(),
+                );
+
+                self.flag_manager.on_death(
+                    message.killer_id,
+                    message.killed_id,
+                    message.flag_transfer,
+                    &mut self.player_manager,
+                );
+
+                if message.killed_id == self.player_id
+                    && let Some(settings) = &self.settings
+                {
+                    self.respawn_tick = Some(LocalTick::now() + settings.enter_delay as i32);
                 }
+
+                self.events.push_back(BotEvent::Kill {
+                    killer: message.killer_id,
+                    killed: message.killed_id,
+                    bounty: message.bounty,
+                    flag_transfer: message.flag_transfer,
+                });
             }
             GameServerMessage::PlayerFrequencyChange(change) => {
                 if let Some(player) = self.player_manager.get_mut(&change.player_id) {
                     player.frequency = change.frequency;
                 }
+
+                self.events.push_back(BotEvent::FrequencyChange {
+                    player_id: change.player_id,
+                    frequency: change.frequency,
+                });
             }
             GameServerMessage::PlayerTeamAndShipChange(change) => {
                 if let Some(player) = self.player_manager.get_mut(&change.player_id) {
                     player.ship = change.ship;
                     player.frequency = change.frequency;
                 }
+
+                self.player_manager.detach_all(&change.player_id);
+
+                // A new ship starts somewhere else, so old updates would only skew the prediction.
+                if let Some(player) = self.player_manager.get_mut(&change.player_id) {
+                    player.history.clear();
+                }
+
+                if change.ship == Ship::Spectator {
+                    self.observer.remove_target(change.player_id);
+                }
+
+                if change.player_id == self.player_id {
+                    self.ship = change.ship;
+                    self.respawn_tick = None;
+                    self.spawn_ship();
+                }
+
+                self.events.push_back(BotEvent::ShipChange {
+                    player_id: change.player_id,
+                    ship: change.ship,
+                    frequency: change.frequency,
+                });
             }
             GameServerMessage::MapInformation(info) => {
                 println!("Map name: {}", info.filename);
 
                 self.connection.state = ConnectionState::MapDownload;
 
-                let chat = SendChatMessage::public("?arena");
-                self.connection.send_reliable(&chat)?;
+                // The arena list marks the arena we are in, which is the only way to learn its name
+                // after joining any public arena.
+                if self.arena_name.is_empty() {
+                    self.request_arena_list()?;
+                }
 
                 let map_path = get_zone_path(&self.zone, &info.filename);
                 let map_data = fs::read(map_path);
@@ -430,6 +1276,10 @@ impl Client {
                     if checksum == info.checksum {
                         if let Some(new_map) = Map::new(info.checksum, &info.filename, &map_data) {
                             self.map = new_map;
+                            self.events.push_back(BotEvent::MapLoaded {
+                                filename: info.filename.clone(),
+                                checksum: info.checksum,
+                            });
                         } else {
                             println!("Map read errorr: failed to load tiles");
                         }
@@ -469,6 +1319,10 @@ impl Client {
                                 Map::new(self.map.checksum, &self.map.filename, &inflated)
                             {
                                 self.map = new_map;
+                                self.events.push_back(BotEvent::MapLoaded {
+                                    filename: self.map.filename.clone(),
+                                    checksum: self.map.checksum,
+                                });
                             } else {
                                 println!("Map read error: failed to load tiles");
                             }
@@ -480,7 +1334,183 @@ impl Client {
                 }
             }
             GameServerMessage::ArenaDirectory(directory) => {
-                println!("directory: {:?}", directory);
+                // Joining any public arena doesn't tell us which one we ended up in, so take it from the list.
+                if let Some(current) = directory.entries.iter().find(|entry| entry.current) {
+                    self.arena_name = current.name.clone();
+                }
+
+                self.arena_list = directory.entries.clone();
+                self.events
+                    .push_back(BotEvent::ArenaList(directory.entries.clone()));
+            }
+            GameServerMessage::FlagPosition(message) => {
+                self.flag_manager.on_position(
+                    message.flag_id,
+                    message.x,
+                    message.y,
+                    message.owner_freq,
+                );
+
+                self.events.push_back(BotEvent::FlagPosition {
+                    flag_id: message.flag_id,
+                    x: message.x,
+                    y: message.y,
+                    owner_freq: message.owner_freq,
+                });
+            }
+            GameServerMessage::FlagClaim(message) => {
+                // Flags that can't be carried are turf flags.
+                let carriable = self
+                    .settings
+                    .as_ref()
+                    .is_none_or(|settings| settings.carry_flags > 0);
+
+                self.flag_manager.on_claim(
+                    message.flag_id,
+                    message.player_id,
+                    carriable,
+                    &mut self.player_manager,
+                );
+
+                self.events.push_back(BotEvent::FlagClaimed {
+                    flag_id: message.flag_id,
+                    player_id: message.player_id,
+                });
+            }
+            GameServerMessage::FlagDrop(message) => {
+                self.flag_manager
+                    .on_drop(message.player_id, &mut self.player_manager);
+
+                self.events
+                    .push_back(BotEvent::FlagDropped(message.player_id));
+            }
+            GameServerMessage::FlagVictory(message) => {
+                self.flag_manager.on_victory(&mut self.player_manager);
+                self.player_manager
+                    .add_flag_points(message.frequency, message.points);
+
+                self.events.push_back(BotEvent::FlagVictory {
+                    frequency: message.frequency,
+                    points: message.points,
+                });
+            }
+            GameServerMessage::FlagReward(message) => {
+                for reward in &message.rewards {
+                    self.player_manager
+                        .add_flag_points(reward.frequency, reward.points as u32);
+
+                    self.events.push_back(BotEvent::FlagReward {
+                        frequency: reward.frequency,
+                        points: reward.points,
+                    });
+                }
+            }
+            GameServerMessage::TurfFlagUpdate(message) => {
+                self.flag_manager.on_turf_update(&message.flag_teams);
+                self.events.push_back(BotEvent::TurfFlagUpdate);
+            }
+            GameServerMessage::PowerballPosition(message) => {
+                self.ball_manager.on_position(message);
+
+                self.events.push_back(BotEvent::BallPosition {
+                    ball_id: message.ball_id,
+                    x: message.x,
+                    y: message.y,
+                    owner_id: message.owner_id,
+                });
+            }
+            GameServerMessage::PowerballGoal(message) => {
+                self.ball_manager
+                    .on_goal(message.frequency, message.team_points);
+
+                self.events.push_back(BotEvent::BallGoal {
+                    frequency: message.frequency,
+                    points: message.team_points,
+                });
+            }
+            GameServerMessage::SpectateData(SpectateDataMessage::ExtraPositionInfo(enabled)) => {
+                self.extra_position_info = *enabled;
+            }
+            GameServerMessage::ScoreUpdate(message) => {
+                self.player_manager.on_score_update(
+                    &message.player_id,
+                    message.kill_points,
+                    message.flag_points,
+                    message.kills,
+                    message.deaths,
+                );
+
+                self.events
+                    .push_back(BotEvent::ScoreChanged(message.player_id));
+            }
+            GameServerMessage::ResetScore(message) => {
+                self.player_manager.reset_score(&message.player_id);
+
+                self.events
+                    .push_back(BotEvent::ScoreReset(message.player_id));
+            }
+            GameServerMessage::PlayerId(message) => {
+                self.player_id = message.id;
+            }
+            GameServerMessage::KothSetTimer(message) => {
+                self.koth.set_timer(LocalTick::now(), message.timer);
+            }
+            GameServerMessage::KothAddTime(message) => {
+                self.koth.add_time(message.added_time);
+            }
+            GameServerMessage::KothReset(message) => {
+                let players = self.player_manager.players.keys().copied();
+
+                self.koth.on_reset(
+                    LocalTick::now(),
+                    self.player_id,
+                    message.player_id,
+                    message.add_crown,
+                    message.timer,
+                    players,
+                );
+
+                self.events.push_back(BotEvent::KothCrown {
+                    player_id: message.player_id,
+                    has_crown: message.add_crown,
+                });
+            }
+            GameServerMessage::BrickDrop(message) => {
+                self.brick_manager.on_drop(message);
+            }
+            GameServerMessage::BrickClear => {
+                self.brick_manager.clear();
+            }
+            GameServerMessage::CollectedPrize(message) => {
+                for _ in 0..message.count {
+                    self.apply_prize(message.prize_id)?;
+                }
+            }
+            GameServerMessage::TurretLinkCreate(message) => {
+                match message.destination_id {
+                    Some(parent) if parent != PlayerId::invalid() => {
+                        self.player_manager.attach(&message.requester_id, &parent);
+                        self.player_manager.move_turrets(&parent);
+                    }
+                    _ => self.player_manager.detach(&message.requester_id),
+                }
+
+                self.events
+                    .push_back(BotEvent::TurretChanged(message.requester_id));
+            }
+            GameServerMessage::TurretLinkDestroy(message) => {
+                self.player_manager.detach_turrets(&message.player_id);
+            }
+            GameServerMessage::Redirect(redirect) => {
+                self.redirect(redirect)?;
+            }
+            GameServerMessage::ShipReset => {
+                self.respawn_tick = None;
+                self.spawn_ship();
+            }
+            GameServerMessage::SetShipCoordinates(message) => {
+                self.ship_state
+                    .set_position(message.x as u32 * 16 + 8, message.y as u32 * 16 + 8);
             }
             _ => {}
         }
@@ -488,6 +1518,86 @@ impl Client {
         Ok(())
     }
 
+    // Tears down the current connection and reconnects to the redirect target with the same credentials.
+    fn redirect(&mut self, redirect: &RedirectMessage) -> anyhow::Result<()> {
+        let now = LocalTick::now();
+
+        if now.diff(&self.last_redirect_tick) > REDIRECT_RESET_TICKS {
+            self.redirect_count = 0;
+        }
+
+        if self.redirect_count >= MAX_REDIRECTS {
+            println!("Ignoring redirect: too many redirects in a short period of time.");
+            return Ok(());
+        }
+
+        let remote_ip = redirect.ip.to_string();
+        let remote_addr = SocketAddr::new(IpAddr::V4(redirect.ip), redirect.port);
+
+        // A redirect to the current server is only a loop if it doesn't move us to another arena either.
+        if remote_addr == self.connection.remote_addr {
+            let arena_request = redirect.arena_request();
+
+            if matches!(arena_request, ArenaRequest::AnyPublic)
+                || arena_request == self.arena_request
+            {
+                println!("Ignoring redirect: target is the current server and arena.");
+                return Ok(());
+            }
+
+            println!("Redirecting to arena {:?}", arena_request);
+
+            self.redirect_count += 1;
+            self.last_redirect_tick = now;
+
+            return self.join_arena(arena_request);
+        }
+
+        println!("Redirecting to {}", remote_addr);
+
+        let disconnect = DisconnectMessage {};
+        self.connection.send(&disconnect)?;
+
+        let encryption_version = self.connection.encryption_version;
+        self.connection = Connection::new(&remote_ip, redirect.port, encryption_version)?;
+
+        self.redirect_count += 1;
+        self.last_redirect_tick = now;
+
+        // Keep the originally requested arena unless the redirect points to a specific one.
+        if !matches!(redirect.arena_request(), ArenaRequest::AnyPublic) {
+            self.arena_request = redirect.arena_request();
+        }
+        self.arena_name = self.arena_request.arena_name().unwrap_or_default();
+        self.login_id = redirect.login_id;
+
+        self.reset_arena_state();
+
+        Ok(())
+    }
+
+    // Clears everything tied to the current arena before joining another one.
+    fn reset_arena_state(&mut self) {
+        self.map = Map::empty(0, "");
+        self.settings = None;
+        self.player_manager = PlayerManager::new();
+        self.flag_manager = FlagManager::new();
+        self.ball_manager = BallManager::new();
+        self.koth = Koth::new();
+        self.brick_manager = BrickManager::new();
+        self.doors = Doors::new();
+        self.weapon_manager = WeaponManager::new();
+        self.player_id = PlayerId::invalid();
+        self.observer.reset();
+        self.extra_position_info = false;
+        self.ship_state = ShipState::new();
+        self.respawn_tick = None;
+        self.movement_goal = None;
+        self.arena_list.clear();
+        // Queued chat was written for the old arena, and player ids in it may belong to someone else now.
+        self.chat_queue.clear();
+    }
+
     fn process_message(&mut self, message: ServerMessage) -> anyhow::Result<()> {
         match message {
             ServerMessage::Core(core_message) => self.process_core_message(&core_message),
@@ -3,7 +3,7 @@ use std::convert::From;
 use std::ops::{Add, Sub};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub struct LocalTick {
     value: u32,
 }
@@ -130,7 +130,7 @@ impl From<u32> for LocalTick {
     }
 }
 
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub struct ServerTick {
     value: u32,
 }
@@ -0,0 +1,211 @@
+use crate::client::Client;
+use crate::event::BotEvent;
+use crate::net::packet::c2s::SendChatMessage;
+use crate::net::packet::s2c::ChatKind;
+use crate::player::PlayerId;
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccessLevel {
+    User,
+    Mod,
+    Owner,
+}
+
+// Player names are matched case insensitively. Anyone not in the list has User access.
+#[derive(Default)]
+pub struct AccessList {
+    levels: HashMap<String, AccessLevel>,
+}
+
+impl AccessList {
+    pub fn new() -> AccessList {
+        AccessList {
+            levels: HashMap::new(),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, level: AccessLevel) {
+        self.levels.insert(name.to_lowercase(), level);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.levels.remove(&name.to_lowercase());
+    }
+
+    pub fn get(&self, name: &str) -> AccessLevel {
+        self.levels
+            .get(&name.to_lowercase())
+            .copied()
+            .unwrap_or(AccessLevel::User)
+    }
+}
+
+// Where a command came from, so the reply can be sent back on the same channel.
+#[derive(Clone, Debug)]
+pub enum ReplyTarget {
+    Private(PlayerId),
+    RemotePrivate(String),
+    Team,
+}
+
+pub struct Command {
+    pub name: String,
+    // Everything after the command name, with surrounding whitespace removed.
+    pub arguments: String,
+
+    pub sender: PlayerId,
+    pub sender_name: String,
+    pub access: AccessLevel,
+    pub reply_target: ReplyTarget,
+}
+
+impl Command {
+    pub fn args(&self) -> Vec<&str> {
+        self.arguments.split_whitespace().collect()
+    }
+
+    pub fn reply(&self, client: &mut Client, text: &str) -> anyhow::Result<()> {
+        match &self.reply_target {
+            ReplyTarget::Private(player_id) => {
+                client.send_chat(&SendChatMessage::private(*player_id, text))
+            }
+            ReplyTarget::RemotePrivate(name) => {
+                client.send_chat(&SendChatMessage::remote_private(name, text))
+            }
+            ReplyTarget::Team => client.send_chat(&SendChatMessage::team(text)),
+        }
+    }
+}
+
+pub type CommandHandler = Box<dyn FnMut(&mut Client, &Command) -> anyhow::Result<()>>;
+
+struct CommandEntry {
+    access: AccessLevel,
+    description: String,
+    handler: CommandHandler,
+}
+
+// Dispatches chat commands such as "!follow name" to registered handlers.
+// Commands are accepted from private, remote private and team chat.
+pub struct CommandRouter {
+    pub prefix: String,
+    pub access_list: AccessList,
+
+    commands: HashMap<String, CommandEntry>,
+}
+
+impl CommandRouter {
+    pub fn new(prefix: &str) -> CommandRouter {
+        CommandRouter {
+            prefix: prefix.to_owned(),
+            access_list: AccessList::new(),
+            commands: HashMap::new(),
+        }
+    }
+
+    // Registers a handler that can be used by anyone with at least the given access level.
+    // Registering the same name again replaces the previous handler.
+    pub fn register<F>(&mut self, name: &str, access: AccessLevel, description: &str, handler: F)
+    where
+        F: FnMut(&mut Client, &Command) -> anyhow::Result<()> + 'static,
+    {
+        self.commands.insert(
+            name.to_lowercase(),
+            CommandEntry {
+                access,
+                description: description.to_owned(),
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    // Returns true if the event was a command that was dispatched.
+    pub fn process(&mut self, client: &mut Client, event: &BotEvent) -> anyhow::Result<bool> {
+        let BotEvent::Chat {
+            kind,
+            sender,
+            sender_name: Some(sender_name),
+            squad: None,
+            message,
+            ..
+        } = event
+        else {
+            return Ok(false);
+        };
+
+        let reply_target = match kind {
+            ChatKind::Private => ReplyTarget::Private(*sender),
+            ChatKind::RemotePrivate => ReplyTarget::RemotePrivate(sender_name.clone()),
+            ChatKind::Team => ReplyTarget::Team,
+            _ => return Ok(false),
+        };
+
+        let sender_name = sender_name.clone();
+        let text = message.as_str();
+
+        let Some(text) = text.strip_prefix(self.prefix.as_str()) else {
+            return Ok(false);
+        };
+
+        let (name, arguments) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+
+        if name.is_empty() {
+            return Ok(false);
+        }
+
+        let command = Command {
+            name: name.to_lowercase(),
+            arguments: arguments.trim().to_owned(),
+            sender: *sender,
+            access: self.access_list.get(&sender_name),
+            sender_name,
+            reply_target,
+        };
+
+        if let Some(entry) = self.commands.get_mut(&command.name) {
+            // Commands above the sender's access level are ignored as if they don't exist.
+            if command.access < entry.access {
+                return Ok(false);
+            }
+
+            if let Err(e) = (entry.handler)(client, &command) {
+                command.reply(client, &format!("Error: {}", e))?;
+            }
+
+            return Ok(true);
+        }
+
+        if command.name == "help" {
+            self.send_help(client, &command)?;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    // Lists the commands that the sender has access to.
+    fn send_help(&self, client: &mut Client, command: &Command) -> anyhow::Result<()> {
+        let mut names: Vec<&String> = self
+            .commands
+            .iter()
+            .filter(|(_, entry)| entry.access <= command.access)
+            .map(|(name, _)| name)
+            .collect();
+
+        names.sort();
+
+        for name in names {
+            let entry = &self.commands[name];
+
+            if entry.description.is_empty() {
+                command.reply(client, &format!("{}{}", self.prefix, name))?;
+            } else {
+                let text = format!("{}{} - {}", self.prefix, name, entry.description);
+                command.reply(client, &text)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use crate::net::packet::c2s::{
+    ArenaRequest, EncryptionClientVersion, RegistrationFormMessage, RegistrationSex,
+};
+use crate::ship::Ship;
+use anyhow::{Result, anyhow};
+
+// Config files are ini style. Each [section] is a named profile, and keys that appear before
+// the first section are defaults shared by every profile. A file without any sections has a
+// single profile named default built from its keys.
+//
+//   zone = local
+//   ip = 127.0.0.1
+//
+//   [test]
+//   username = test
+//   password_env = PUPPET_PASSWORD
+//   arena = duel
+//   ship = warbird
+//   owners = name1, name2
+pub struct Config {
+    pub profiles: Vec<Profile>,
+}
+
+pub struct Profile {
+    pub name: String,
+
+    pub username: String,
+    pub password: String,
+    // Environment variable holding the password. It's only read once a profile has been picked, so other
+    // profiles in the file don't need their variables set.
+    pub password_env: Option<String>,
+    pub zone: String,
+    pub remote_ip: String,
+    pub remote_port: u16,
+    pub encryption: EncryptionClientVersion,
+
+    pub arena: ArenaRequest,
+    pub ship: Ship,
+
+    pub registration: RegistrationFormMessage,
+
+    // Player names that are given owner or mod access to commands.
+    pub owners: Vec<String>,
+    pub mods: Vec<String>,
+
+    pub chat_log: bool,
+    // Include arena and system messages in the chat log.
+    pub chat_log_arena: bool,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Config> {
+        let data = std::fs::read_to_string(path)?;
+        Config::parse(&data)
+    }
+
+    pub fn parse(data: &str) -> Result<Config> {
+        let mut defaults: Vec<(String, String)> = Vec::new();
+        let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+
+        for (index, line) in data.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| anyhow!("line {}: unterminated section name", index + 1))?;
+
+                sections.push((name.trim().to_owned(), Vec::new()));
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected key = value", index + 1))?;
+
+            let entry = (key.trim().to_lowercase(), value.trim().to_owned());
+
+            match sections.last_mut() {
+                Some((_, values)) => values.push(entry),
+                None => defaults.push(entry),
+            }
+        }
+
+        if sections.is_empty() && !defaults.is_empty() {
+            sections.push(("default".to_owned(), Vec::new()));
+        }
+
+        let mut profiles = Vec::new();
+
+        for (name, values) in &sections {
+            let mut profile = Profile::new(name);
+
+            for (key, value) in defaults.iter().chain(values.iter()) {
+                profile
+                    .set(key, value)
+                    .map_err(|e| anyhow!("profile {}: {}", name, e))?;
+            }
+
+            profiles.push(profile);
+        }
+
+        Ok(Config { profiles })
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn take_profile(&mut self, name: &str) -> Option<Profile> {
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)?;
+        Some(self.profiles.swap_remove(index))
+    }
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Profile {
+        Profile {
+            name: name.to_owned(),
+            username: "test".to_owned(),
+            password: "none".to_owned(),
+            password_env: None,
+            zone: "local".to_owned(),
+            remote_ip: "127.0.0.1".to_owned(),
+            remote_port: 5000,
+            encryption: EncryptionClientVersion::Subspace,
+            arena: ArenaRequest::AnyPublic,
+            ship: Ship::Spectator,
+            registration: RegistrationFormMessage::new(
+                "puppet",
+                "puppet@puppet.com",
+                "puppet city",
+                "puppet state",
+                RegistrationSex::Female,
+                20,
+            ),
+            owners: Vec::new(),
+            mods: Vec::new(),
+            chat_log: false,
+            chat_log_arena: false,
+        }
+    }
+
+    // Sets a single value by its config key. This is shared by the config file and command line overrides.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "username" => self.username = value.to_owned(),
+            "password" => {
+                self.password = value.to_owned();
+                self.password_env = None;
+            }
+            "password_env" => self.password_env = Some(value.to_owned()),
+            "zone" => self.zone = value.to_owned(),
+            "ip" => self.remote_ip = value.to_owned(),
+            "port" => self.remote_port = value.parse()?,
+            "encryption" => self.encryption = parse_encryption(value)?,
+            "arena" => self.arena = parse_arena(value),
+            "ship" => self.ship = parse_ship(value)?,
+            "real_name" => self.registration.real_name = value.to_owned(),
+            "email" => self.registration.email = value.to_owned(),
+            "city" => self.registration.city = value.to_owned(),
+            "state" => self.registration.state = value.to_owned(),
+            "sex" => self.registration.sex = parse_sex(value)?,
+            "age" => self.registration.age = value.parse()?,
+            "owners" => self.owners = parse_names(value),
+            "mods" => self.mods = parse_names(value),
+            "chat_log" => self.chat_log = parse_bool(value)?,
+            "chat_log_arena" => self.chat_log_arena = parse_bool(value)?,
+            _ => return Err(anyhow!("unknown key {}", key)),
+        }
+
+        Ok(())
+    }
+
+    // Replaces the password with the one from password_env, if that was set.
+    pub fn resolve_password(&mut self) -> Result<()> {
+        if let Some(name) = self.password_env.take() {
+            self.password = std::env::var(&name)
+                .map_err(|_| anyhow!("environment variable {} is not set", name))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Empty means any public arena, a number is a specific public arena and anything else is an arena name.
+pub fn parse_arena(value: &str) -> ArenaRequest {
+    if value.is_empty() {
+        return ArenaRequest::AnyPublic;
+    }
+
+    match value.parse::<u16>() {
+        Ok(number) => ArenaRequest::SpecificPublic(number),
+        Err(_) => ArenaRequest::named(value),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(anyhow!("invalid boolean {}", value)),
+    }
+}
+
+fn parse_names(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_owned())
+        .collect()
+}
+
+fn parse_ship(value: &str) -> Result<Ship> {
+    match value.to_lowercase().as_str() {
+        "warbird" => Ok(Ship::Warbird),
+        "javelin" => Ok(Ship::Javelin),
+        "spider" => Ok(Ship::Spider),
+        "leviathan" => Ok(Ship::Leviathan),
+        "terrier" => Ok(Ship::Terrier),
+        "weasel" => Ok(Ship::Weasel),
+        "lancaster" => Ok(Ship::Lancaster),
+        "shark" => Ok(Ship::Shark),
+        "spectator" => Ok(Ship::Spectator),
+        // Ships can also be selected by number as they are in game, 1 = warbird through 9 = spectator.
+        number => match number.parse::<u8>() {
+            Ok(number) if (1..=9).contains(&number) => Ok(Ship::from_network_value(number - 1)),
+            _ => Err(anyhow!("invalid ship {}", value)),
+        },
+    }
+}
+
+fn parse_sex(value: &str) -> Result<RegistrationSex> {
+    match value.to_lowercase().as_str() {
+        "m" | "male" => Ok(RegistrationSex::Male),
+        "f" | "female" => Ok(RegistrationSex::Female),
+        _ => Err(anyhow!("invalid sex {}", value)),
+    }
+}
+
+fn parse_encryption(value: &str) -> Result<EncryptionClientVersion> {
+    match value.to_lowercase().as_str() {
+        "subspace" | "vie" => Ok(EncryptionClientVersion::Subspace),
+        "continuum_classic" => Ok(EncryptionClientVersion::ContinuumClassic),
+        "continuum" => Ok(EncryptionClientVersion::Continuum),
+        _ => Err(anyhow!("invalid encryption {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_level_keys_only() {
+        let config = Config::parse("username = bot\nzone = ssce\nport = 5005\nship = 3\n").unwrap();
+
+        assert_eq!(config.profiles.len(), 1);
+
+        let profile = config.profile("default").unwrap();
+        assert_eq!(profile.username, "bot");
+        assert_eq!(profile.zone, "ssce");
+        assert_eq!(profile.remote_port, 5005);
+        assert_eq!(profile.ship, Ship::Spider);
+    }
+
+    #[test]
+    fn defaults_shared_by_sections() {
+        let config = Config::parse(
+            "zone = ssce\n\n[one]\nusername = a\n\n[two]\nusername = b\nzone = other\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.profiles.len(), 2);
+        assert!(config.profile("default").is_none());
+        assert_eq!(config.profile("one").unwrap().zone, "ssce");
+        assert_eq!(config.profile("two").unwrap().zone, "other");
+    }
+
+    #[test]
+    fn password_env_read_on_resolve() {
+        let data = "[one]\npassword_env = PUPPET_TEST_UNSET_ONE\n\n[two]\npassword_env = PUPPET_TEST_UNSET_TWO\n";
+        let mut config = Config::parse(data).unwrap();
+
+        let mut profile = config.take_profile("one").unwrap();
+        assert!(profile.resolve_password().is_err());
+
+        let mut profile = config.take_profile("two").unwrap();
+        profile.set("password", "override").unwrap();
+        profile.resolve_password().unwrap();
+        assert_eq!(profile.password, "override");
+    }
+}
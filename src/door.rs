@@ -0,0 +1,87 @@
+use crate::clock::ServerTick;
+
+// Door state is one bit per door tile type, starting at TILE_ID_FIRST_DOOR. A set bit means the door is open.
+//
+// Doors are deterministic. The server hands out a seed and a start time in the synchronization request,
+// and every client advances the same random generator each time door_delay ticks pass. The random modes
+// (-1 weighted, -2 fully random) aren't ported from the client yet, so only fixed door modes are known.
+// For the others the state is unknown and callers fall back to treating doors as plain map tiles.
+pub struct Doors {
+    pub mode: i16,
+    pub delay: i32,
+
+    // Kept from the synchronization request for when the random modes are supported.
+    pub seed: u32,
+    pub start_tick: ServerTick,
+}
+
+impl Doors {
+    pub fn new() -> Doors {
+        Doors {
+            mode: 0,
+            delay: 0,
+            seed: 0,
+            start_tick: ServerTick::empty(),
+        }
+    }
+
+    // The seed and the arena settings can arrive in either order.
+    pub fn seed(&mut self, seed: u32, timestamp: ServerTick) {
+        self.seed = seed;
+        self.start_tick = timestamp;
+    }
+
+    // Takes door_mode and door_delay from the arena settings.
+    pub fn set_mode(&mut self, mode: i16, delay: i16) {
+        self.mode = mode;
+        self.delay = delay as i32;
+    }
+
+    // Door state at any server tick, or None when the door mode isn't supported.
+    pub fn state_at(&self, _tick: ServerTick) -> Option<u8> {
+        if self.mode >= 0 {
+            return Some(self.mode as u8);
+        }
+
+        None
+    }
+
+    pub fn is_open(&self, door_index: u8, tick: ServerTick) -> Option<bool> {
+        self.state_at(tick)
+            .map(|state| state & (1 << door_index) != 0)
+    }
+}
+
+impl Default for Doors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_doors() {
+        let mut doors = Doors::new();
+        doors.set_mode(0x0F, 100);
+        doors.seed(12345, ServerTick::new(1000, 0));
+
+        assert_eq!(doors.state_at(ServerTick::new(5000, 0)), Some(0x0F));
+        assert_eq!(doors.is_open(3, ServerTick::new(1100, 0)), Some(true));
+        assert_eq!(doors.is_open(4, ServerTick::new(1100, 0)), Some(false));
+    }
+
+    #[test]
+    fn random_doors_unknown() {
+        let mut doors = Doors::new();
+        doors.seed(12345, ServerTick::new(1000, 0));
+
+        doors.set_mode(-1, 100);
+        assert_eq!(doors.is_open(0, ServerTick::new(1100, 0)), None);
+
+        doors.set_mode(-2, 100);
+        assert_eq!(doors.state_at(ServerTick::new(1100, 0)), None);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::net::packet::c2s::ArenaRequest;
+use crate::net::packet::s2c::{ArenaDirectoryEntry, ChatKind, LoginResponse};
+use crate::player::PlayerId;
+use crate::ship::Ship;
+use crate::weapon::{Explosion, WeaponData};
+
+// Events reported by the client after it has applied a server message to its own state.
+// The player state can be looked up through the client's player manager when handling an event.
+#[derive(Clone, Debug)]
+pub enum BotEvent {
+    LoginResult(LoginResponse),
+    // The arena list was received. The entries are also stored on the client.
+    ArenaList(Vec<ArenaDirectoryEntry>),
+    // Sent after leaving the old arena when switching arenas, before anything from the new arena arrives.
+    ArenaChanged {
+        arena_request: ArenaRequest,
+    },
+    MapLoaded {
+        filename: String,
+        checksum: u32,
+    },
+    PlayerEntered(PlayerId),
+    // The player is already removed from the player manager, so the name is included.
+    PlayerLeft {
+        player_id: PlayerId,
+        name: String,
+    },
+    Chat {
+        kind: ChatKind,
+        sender: PlayerId,
+        // Resolved through the player manager, or parsed from the text for remote private, squad
+        // and channel messages. None for arena messages and unknown senders.
+        sender_name: Option<String>,
+        squad: Option<String>,
+        channel: Option<u8>,
+        // The message body without any sender prefix.
+        message: String,
+    },
+    Kill {
+        killer: PlayerId,
+        killed: PlayerId,
+        bounty: u16,
+        flag_transfer: u16,
+    },
+    ShipChange {
+        player_id: PlayerId,
+        ship: Ship,
+        frequency: u16,
+    },
+    FrequencyChange {
+        player_id: PlayerId,
+        frequency: u16,
+    },
+    PositionUpdated(PlayerId),
+    // The player attached to or detached from another player. The new parent is the player's attach_parent.
+    TurretChanged(PlayerId),
+    ScoreChanged(PlayerId),
+    // PlayerId::invalid() means every player's score was reset.
+    ScoreReset(PlayerId),
+    FlagPosition {
+        flag_id: u16,
+        x: u16,
+        y: u16,
+        owner_freq: u16,
+    },
+    FlagClaimed {
+        flag_id: u16,
+        player_id: PlayerId,
+    },
+    FlagDropped(PlayerId),
+    FlagVictory {
+        frequency: u16,
+        points: u32,
+    },
+    FlagReward {
+        frequency: u16,
+        points: u16,
+    },
+    // Turf flag owners changed. The new owners are in the flag manager.
+    TurfFlagUpdate,
+    // PlayerId::invalid() means the crown was given to or taken from everyone.
+    KothCrown {
+        player_id: PlayerId,
+        has_crown: bool,
+    },
+    // Our crown timer ran out and the server was told.
+    KothExpired,
+    BallPosition {
+        ball_id: u8,
+        x: u16,
+        y: u16,
+        owner_id: PlayerId,
+    },
+    BallGoal {
+        frequency: u16,
+        points: u32,
+    },
+    WeaponFired {
+        player_id: PlayerId,
+        weapon: WeaponData,
+    },
+    // A simulated bomb, mine or thor reached the end of its flight.
+    Explosion(Explosion),
+}
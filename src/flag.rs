@@ -0,0 +1,179 @@
+use crate::player::{PlayerId, PlayerManager};
+use std::collections::HashMap;
+
+// Frequency used by the server for flags that nobody owns.
+pub const NEUTRAL_FREQUENCY: u16 = 0xFFFF;
+
+#[derive(Clone, Debug)]
+pub struct Flag {
+    pub id: u16,
+    // Tile coordinates of the flag when it is on the ground. This is where it was last seen while it is carried.
+    pub x: u16,
+    pub y: u16,
+    pub owner_freq: u16,
+    pub carrier: Option<PlayerId>,
+}
+
+// Tracks every flag in the arena and keeps each player's flag_count in sync with the flags they carry.
+pub struct FlagManager {
+    pub flags: HashMap<u16, Flag>,
+}
+
+impl FlagManager {
+    pub fn new() -> FlagManager {
+        FlagManager {
+            flags: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, flag_id: u16) -> Option<&Flag> {
+        self.flags.get(&flag_id)
+    }
+
+    pub fn carried_by(&self, player_id: PlayerId) -> impl Iterator<Item = &Flag> {
+        self.flags
+            .values()
+            .filter(move |flag| flag.carrier == Some(player_id))
+    }
+
+    pub fn owned_by(&self, frequency: u16) -> impl Iterator<Item = &Flag> {
+        self.flags
+            .values()
+            .filter(move |flag| flag.owner_freq == frequency)
+    }
+
+    pub fn on_ground(&self) -> impl Iterator<Item = &Flag> {
+        self.flags.values().filter(|flag| flag.carrier.is_none())
+    }
+
+    // The flag was placed on the ground. This is sent for new flags and for every flag dropped by a carrier.
+    pub fn on_position(&mut self, flag_id: u16, x: u16, y: u16, owner_freq: u16) {
+        let flag = self.flags.entry(flag_id).or_insert(Flag {
+            id: flag_id,
+            x,
+            y,
+            owner_freq,
+            carrier: None,
+        });
+
+        flag.x = x;
+        flag.y = y;
+        flag.owner_freq = owner_freq;
+        flag.carrier = None;
+    }
+
+    // Carriable flags are picked up by the player. Turf flags stay in place and change owner.
+    pub fn on_claim(
+        &mut self,
+        flag_id: u16,
+        player_id: PlayerId,
+        carriable: bool,
+        player_manager: &mut PlayerManager,
+    ) {
+        let Some(player) = player_manager.get_mut(&player_id) else {
+            return;
+        };
+
+        let flag = self.flags.entry(flag_id).or_insert(Flag {
+            id: flag_id,
+            x: (player.position.x / 16) as u16,
+            y: (player.position.y / 16) as u16,
+            owner_freq: NEUTRAL_FREQUENCY,
+            carrier: None,
+        });
+
+        flag.owner_freq = player.frequency;
+
+        if carriable {
+            flag.carrier = Some(player_id);
+            player.flag_count += 1;
+        }
+    }
+
+    // The player dropped every flag they were carrying. Their new positions are sent separately.
+    pub fn on_drop(&mut self, player_id: PlayerId, player_manager: &mut PlayerManager) {
+        self.release(player_id);
+
+        if let Some(player) = player_manager.get_mut(&player_id) {
+            player.flag_count = 0;
+        }
+    }
+
+    // Flags carried by the killed player are transferred to the killer.
+    pub fn on_death(
+        &mut self,
+        killer_id: PlayerId,
+        killed_id: PlayerId,
+        flag_transfer: u16,
+        player_manager: &mut PlayerManager,
+    ) {
+        let killer_freq = player_manager
+            .get(&killer_id)
+            .map(|killer| killer.frequency);
+
+        for flag in self.flags.values_mut() {
+            if flag.carrier == Some(killed_id) {
+                match killer_freq {
+                    Some(frequency) if flag_transfer > 0 => {
+                        flag.carrier = Some(killer_id);
+                        flag.owner_freq = frequency;
+                    }
+                    _ => flag.carrier = None,
+                }
+            }
+        }
+
+        if let Some(killed) = player_manager.get_mut(&killed_id) {
+            killed.flag_count = 0;
+        }
+
+        if let Some(killer) = player_manager.get_mut(&killer_id) {
+            killer.flag_count += flag_transfer;
+        }
+    }
+
+    // Carried flags are returned to the server when a player leaves.
+    pub fn on_player_leave(&mut self, player_id: PlayerId) {
+        self.release(player_id);
+    }
+
+    // Turf updates list the owner of every flag, indexed by flag id.
+    pub fn on_turf_update(&mut self, flag_teams: &[u16]) {
+        for (flag_id, &owner_freq) in flag_teams.iter().enumerate() {
+            let flag_id = flag_id as u16;
+
+            let flag = self.flags.entry(flag_id).or_insert(Flag {
+                id: flag_id,
+                x: 0,
+                y: 0,
+                owner_freq,
+                carrier: None,
+            });
+
+            flag.owner_freq = owner_freq;
+        }
+    }
+
+    // The flag game was won, so every flag is taken off the map until the next game starts.
+    pub fn on_victory(&mut self, player_manager: &mut PlayerManager) {
+        self.flags.clear();
+
+        for player in player_manager.players.values_mut() {
+            player.flag_count = 0;
+        }
+    }
+
+    fn release(&mut self, player_id: PlayerId) {
+        for flag in self.flags.values_mut() {
+            if flag.carrier == Some(player_id) {
+                flag.carrier = None;
+            }
+        }
+    }
+}
+
+impl Default for FlagManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
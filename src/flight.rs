@@ -0,0 +1,752 @@
+use crate::arena_settings::{ArenaSettings, ShipSettings};
+use crate::clock::ServerTick;
+use crate::map::Map;
+use crate::net::packet::c2s::PositionMessage;
+use crate::net::packet::s2c::{ExtraPositionData, ItemSet};
+use crate::player::StatusFlags;
+use crate::prediction::{self, DEFAULT_SHIP_RADIUS};
+use crate::prize::Prize;
+use crate::weapon::{FireKind, WeaponData, WeaponKind};
+
+// A full turn is 40 directions of 1000 rotation units each. A rotation setting of 400 turns once per second.
+pub const ROTATION_PER_TURN: i32 = 40000;
+// Wormholes are 5x5 tiles, so their pull comes from 40 pixels in from the corner, the middle of the third tile.
+const WORMHOLE_CENTER_OFFSET: i64 = 40000;
+// Wormholes don't pull ships that are further away than this many pixels. This is an estimate rather than
+// something taken from the ship settings.
+const WORMHOLE_RANGE: i64 = 1024;
+
+// How close we need to get to a move target before stopping there, in pixels.
+const ARRIVE_DISTANCE: f32 = 8.0;
+// Speed below which the ship counts as stopped, in pixels per 10 seconds.
+const STOPPED_SPEED: f32 = 20.0;
+// Thrust is only applied once we face within this many rotation units of where we want to accelerate.
+const THRUST_ANGLE: i32 = 3000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Turn {
+    #[default]
+    None,
+    Left,
+    Right,
+}
+
+// Something the ship steers toward by itself, setting its own input each tick until it is reached.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MovementGoal {
+    // Rotation in the same units as ShipState::rotation.
+    Rotate(i32),
+    // Target position in pixels.
+    MoveTo(u32, u32),
+    Stop,
+}
+
+// What the ship is being told to do this tick.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ShipInput {
+    pub thrust: bool,
+    pub reverse: bool,
+    pub left: bool,
+    pub right: bool,
+    pub afterburner: bool,
+}
+
+// Our own ship as simulated locally. Positions are in thousandths of a pixel, velocity in pixels per 10 seconds
+// and energy in thousandths.
+#[derive(Clone, Debug, Default)]
+pub struct ShipState {
+    pub x: i64,
+    pub y: i64,
+    pub x_velocity: i64,
+    pub y_velocity: i64,
+    pub rotation: i32,
+    pub energy: i64,
+
+    pub input: ShipInput,
+
+    // Current stats, starting at the ship's initial settings and raised by prizes.
+    pub rotation_rate: u32,
+    pub thrust: u32,
+    pub speed: u32,
+    pub recharge: u32,
+    pub max_energy: u32,
+
+    pub radius: u16,
+    pub bounty: u16,
+    pub status: u8,
+
+    // Gun and bomb levels start at one. Zero means the ship doesn't have the weapon.
+    pub guns: u8,
+    pub bombs: u8,
+    pub multifire: bool,
+    pub bouncing_bullets: bool,
+    pub proximity: bool,
+    pub shrapnel: u8,
+    // Ticks until another weapon can be fired.
+    pub fire_delay: u32,
+    // Whether afterburners were running on the last tick.
+    pub afterburner_active: bool,
+
+    pub items: ItemSet,
+    // Ticks left on the active repel and rocket. Neither can be used again while active.
+    pub repel_ticks: u32,
+    pub rocket_ticks: u32,
+    // Placed portal in pixels and the ticks it has left.
+    pub portal: Option<(u32, u32)>,
+    pub portal_ticks: u32,
+}
+
+impl ShipState {
+    pub fn new() -> ShipState {
+        ShipState::default()
+    }
+
+    // Resets the ship to its initial stats with full energy at the position, which is in pixels.
+    pub fn spawn(&mut self, ship_settings: &ShipSettings, x: u32, y: u32) {
+        *self = ShipState {
+            x: x as i64 * 1000,
+            y: y as i64 * 1000,
+            rotation_rate: ship_settings.initial_rotation as u32,
+            thrust: ship_settings.initial_thrust as u32,
+            speed: ship_settings.initial_speed as u32,
+            recharge: ship_settings.initial_recharge as u32,
+            max_energy: ship_settings.initial_energy as u32,
+            energy: ship_settings.initial_energy as i64 * 1000,
+            radius: if ship_settings.radius == 0 {
+                DEFAULT_SHIP_RADIUS
+            } else {
+                ship_settings.radius
+            },
+            bounty: ship_settings.initial_bounty,
+            guns: ship_settings.initial_guns,
+            bombs: ship_settings.initial_bombs,
+            items: ItemSet {
+                repels: ship_settings.initial_repel,
+                bursts: ship_settings.initial_burst,
+                bricks: ship_settings.initial_brick,
+                rockets: ship_settings.initial_rocket,
+                thors: ship_settings.initial_thor,
+                decoys: ship_settings.initial_decoy,
+                portals: ship_settings.initial_portal,
+                ..ItemSet::default()
+            },
+            input: self.input,
+            ..ShipState::default()
+        };
+    }
+
+    // Moves the ship somewhere else without changing anything else, such as a warp from the server.
+    pub fn set_position(&mut self, x: u32, y: u32) {
+        self.x = x as i64 * 1000;
+        self.y = y as i64 * 1000;
+        self.x_velocity = 0;
+        self.y_velocity = 0;
+    }
+
+    pub fn direction(&self) -> u8 {
+        (self.rotation / 1000) as u8
+    }
+
+    pub fn pixel_x(&self) -> u32 {
+        (self.x / 1000) as u32
+    }
+
+    pub fn pixel_y(&self) -> u32 {
+        (self.y / 1000) as u32
+    }
+
+    // Unit vector for the current rotation. Direction 0 points up.
+    pub fn heading(&self) -> (f32, f32) {
+        let angle = self.rotation as f32 / ROTATION_PER_TURN as f32 * std::f32::consts::TAU;
+        (angle.sin(), -angle.cos())
+    }
+
+    // Runs one tick of movement. Solidity is passed in so doors and bricks can be included.
+    pub fn update<F>(
+        &mut self,
+        ship_settings: &ShipSettings,
+        settings: &ArenaSettings,
+        map: &Map,
+        is_solid: F,
+    ) where
+        F: Fn(u16, u16) -> bool,
+    {
+        self.rotate();
+
+        self.fire_delay = self.fire_delay.saturating_sub(1);
+        self.repel_ticks = self.repel_ticks.saturating_sub(1);
+        self.portal_ticks = self.portal_ticks.saturating_sub(1);
+        if self.portal_ticks == 0 {
+            self.portal = None;
+        }
+
+        // Rockets push the ship forward at their own thrust and speed, ignoring the controls.
+        let rocket = self.rocket_ticks > 0;
+        self.rocket_ticks = self.rocket_ticks.saturating_sub(1);
+
+        // Afterburners run at the ship's maximum stats for as long as there is energy to pay for them.
+        let afterburner = self.input.afterburner
+            && (self.input.thrust || self.input.reverse)
+            && self.energy > ship_settings.afterburner_energy as i64;
+
+        self.afterburner_active = afterburner;
+
+        let (thrust, mut speed) = if rocket {
+            (settings.rocket_thrust as i64, settings.rocket_speed as i64)
+        } else if afterburner {
+            self.energy -= ship_settings.afterburner_energy as i64;
+            (
+                ship_settings.maximum_thrust as i64,
+                ship_settings.maximum_speed as i64,
+            )
+        } else {
+            (self.thrust as i64, self.speed as i64)
+        };
+
+        if rocket || self.input.thrust || self.input.reverse {
+            let (heading_x, heading_y) = self.heading();
+            let sign = if rocket || self.input.thrust {
+                1.0
+            } else {
+                -1.0
+            };
+            let acceleration = (thrust * 10 / 16) as f32 * sign;
+
+            self.x_velocity += (heading_x * acceleration) as i64;
+            self.y_velocity += (heading_y * acceleration) as i64;
+        }
+
+        if self.apply_gravity(ship_settings, map) {
+            speed += ship_settings.gravity_top_speed.max(0) as i64;
+        }
+
+        self.limit_speed(speed);
+
+        if !afterburner {
+            self.energy = (self.energy + self.recharge as i64).min(self.max_energy as i64 * 1000);
+        }
+
+        self.step(settings.bounce_factor, is_solid);
+    }
+
+    fn rotate(&mut self) {
+        let rate = self.rotation_rate as i32;
+
+        if self.input.left {
+            self.rotation -= rate;
+        }
+
+        if self.input.right {
+            self.rotation += rate;
+        }
+
+        self.rotation = self.rotation.rem_euclid(ROTATION_PER_TURN);
+    }
+
+    // Pulls the ship toward nearby wormholes. Returns true if any wormhole was in range.
+    fn apply_gravity(&mut self, ship_settings: &ShipSettings, map: &Map) -> bool {
+        let gravity = ship_settings.gravity as i64;
+        if gravity == 0 {
+            return false;
+        }
+
+        let mut pulled = false;
+
+        for &(tile_x, tile_y) in &map.wormholes {
+            let dx = tile_x as i64 * 16000 + WORMHOLE_CENTER_OFFSET - self.x;
+            let dy = tile_y as i64 * 16000 + WORMHOLE_CENTER_OFFSET - self.y;
+
+            let distance = ((dx * dx + dy * dy) as f64).sqrt() / 1000.0;
+            if distance < 1.0 || distance > WORMHOLE_RANGE as f64 {
+                continue;
+            }
+
+            // Pull falls off with the square of the distance in pixels. Only the gravity strength comes from the
+            // ship settings; the falloff is an estimate of the client's.
+            let strength = (gravity as f64 * 1000.0 / (distance * distance))
+                .clamp(-gravity.abs() as f64, gravity.abs() as f64);
+
+            self.x_velocity += (dx as f64 / 1000.0 / distance * strength) as i64;
+            self.y_velocity += (dy as f64 / 1000.0 / distance * strength) as i64;
+
+            pulled = true;
+        }
+
+        pulled
+    }
+
+    fn limit_speed(&mut self, speed: i64) {
+        let current =
+            ((self.x_velocity * self.x_velocity + self.y_velocity * self.y_velocity) as f64).sqrt();
+
+        if current > speed as f64 && current > 0.0 {
+            let scale = speed as f64 / current;
+            self.x_velocity = (self.x_velocity as f64 * scale) as i64;
+            self.y_velocity = (self.y_velocity as f64 * scale) as i64;
+        }
+    }
+
+    // Moves by one tick of velocity, bouncing off anything solid.
+    fn step<F>(&mut self, bounce_factor: i16, is_solid: F)
+    where
+        F: Fn(u16, u16) -> bool,
+    {
+        if prediction::collides_with(self.x + self.x_velocity, self.y, self.radius, &is_solid) {
+            self.x_velocity = -prediction::bounce(self.x_velocity, bounce_factor);
+            self.y_velocity = prediction::bounce(self.y_velocity, bounce_factor);
+        } else {
+            self.x += self.x_velocity;
+        }
+
+        if prediction::collides_with(self.x, self.y + self.y_velocity, self.radius, &is_solid) {
+            self.x_velocity = prediction::bounce(self.x_velocity, bounce_factor);
+            self.y_velocity = -prediction::bounce(self.y_velocity, bounce_factor);
+        } else {
+            self.y += self.y_velocity;
+        }
+    }
+
+    // Sets the input needed to work toward the goal. Returns true once the goal is reached, with input cleared.
+    pub fn steer(&mut self, goal: MovementGoal) -> bool {
+        self.input = ShipInput::default();
+
+        match goal {
+            MovementGoal::Rotate(rotation) => self.steer_rotation(rotation),
+            MovementGoal::MoveTo(x, y) => {
+                let dx = x as f32 - self.x as f32 / 1000.0;
+                let dy = y as f32 - self.y as f32 / 1000.0;
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                if distance <= ARRIVE_DISTANCE && self.speed_now() <= STOPPED_SPEED {
+                    return true;
+                }
+
+                // Aim for a velocity that leaves room to turn around and brake in time, then accelerate toward the difference.
+                let acceleration = (self.thrust * 10 / 16).max(1) as f32;
+                let desired_speed =
+                    (self.speed as f32).min((acceleration * distance * 1000.0).sqrt());
+                let (desired_x, desired_y) = if distance > 0.0 {
+                    (dx / distance * desired_speed, dy / distance * desired_speed)
+                } else {
+                    (0.0, 0.0)
+                };
+
+                self.steer_velocity(desired_x, desired_y);
+                false
+            }
+            MovementGoal::Stop => {
+                if self.speed_now() <= STOPPED_SPEED {
+                    return true;
+                }
+
+                self.steer_velocity(0.0, 0.0);
+                false
+            }
+        }
+    }
+
+    fn speed_now(&self) -> f32 {
+        ((self.x_velocity * self.x_velocity + self.y_velocity * self.y_velocity) as f32).sqrt()
+    }
+
+    // Turns toward the rotation, finishing the turn exactly when it is less than one tick away.
+    fn steer_rotation(&mut self, rotation: i32) -> bool {
+        let difference = rotation_difference(self.rotation, rotation);
+
+        if difference.abs() <= self.rotation_rate as i32 {
+            self.rotation = rotation.rem_euclid(ROTATION_PER_TURN);
+            return true;
+        }
+
+        if difference < 0 {
+            self.input.left = true;
+        } else {
+            self.input.right = true;
+        }
+
+        false
+    }
+
+    // Accelerates to close the gap between the current and desired velocity, using reverse when the ship
+    // already faces away from where it needs to go.
+    fn steer_velocity(&mut self, desired_x: f32, desired_y: f32) {
+        let error_x = desired_x - self.x_velocity as f32;
+        let error_y = desired_y - self.y_velocity as f32;
+
+        if (error_x * error_x + error_y * error_y).sqrt() <= STOPPED_SPEED {
+            return;
+        }
+
+        let forward = rotation_from_vector(error_x, error_y);
+        let backward = (forward + ROTATION_PER_TURN / 2).rem_euclid(ROTATION_PER_TURN);
+
+        let forward_difference = rotation_difference(self.rotation, forward);
+        let backward_difference = rotation_difference(self.rotation, backward);
+
+        if forward_difference.abs() <= backward_difference.abs() {
+            self.steer_rotation(forward);
+            self.input.thrust = forward_difference.abs() <= THRUST_ANGLE;
+        } else {
+            self.steer_rotation(backward);
+            self.input.reverse = backward_difference.abs() <= THRUST_ANGLE;
+        }
+    }
+
+    // Pays for and returns the weapon if it can be fired now. Flaggers pay the arena's flagger cost and can be
+    // given an extra level, and some ships can't fire at all while their afterburners are running.
+    pub fn fire(
+        &mut self,
+        kind: FireKind,
+        ship_settings: &ShipSettings,
+        settings: &ArenaSettings,
+        flagger: bool,
+    ) -> Option<WeaponData> {
+        if self.fire_delay > 0 {
+            return None;
+        }
+
+        if ship_settings.disable_fast_shooting && self.afterburner_active {
+            return None;
+        }
+
+        let gun_level = match (self.guns, flagger && settings.flagger_gun_upgrade) {
+            (0, _) => None,
+            (guns, true) => Some(guns.min(2)),
+            (guns, false) => Some((guns - 1).min(2)),
+        };
+
+        let bomb_level = match (self.bombs, flagger && settings.flagger_bomb_upgrade) {
+            (0, _) => None,
+            (bombs, true) => Some(bombs.min(2)),
+            (bombs, false) => Some((bombs - 1).min(2)),
+        };
+
+        let (weapon, cost, delay) = match kind {
+            FireKind::Gun | FireKind::Multifire => {
+                let level = gun_level?;
+                let multifire = kind == FireKind::Multifire;
+
+                if multifire && !self.multifire {
+                    return None;
+                }
+
+                let bullet_kind = if self.bouncing_bullets {
+                    WeaponKind::BouncingBullet
+                } else {
+                    WeaponKind::Bullet
+                };
+
+                let (energy, delay) = if multifire {
+                    (
+                        ship_settings.multi_fire_energy,
+                        ship_settings.multi_fire_delay,
+                    )
+                } else {
+                    (
+                        ship_settings.bullet_fire_energy,
+                        ship_settings.bullet_fire_delay,
+                    )
+                };
+
+                (
+                    WeaponData::build(bullet_kind)
+                        .with_level(level)
+                        .with_alternate(multifire),
+                    energy as u32 * (level as u32 + 1),
+                    delay,
+                )
+            }
+            FireKind::Bomb | FireKind::Mine => {
+                let level = bomb_level?;
+                let mine = kind == FireKind::Mine;
+
+                let bomb_kind = if self.proximity {
+                    WeaponKind::ProximityBomb
+                } else {
+                    WeaponKind::Bomb
+                };
+
+                let (energy, upgrade, mut delay) = if mine {
+                    (
+                        ship_settings.mine_fire_energy,
+                        ship_settings.mine_fire_energy_upgrade,
+                        ship_settings.mine_fire_delay,
+                    )
+                } else {
+                    (
+                        ship_settings.bomb_fire_energy,
+                        ship_settings.bomb_fire_energy_upgrade,
+                        ship_settings.bomb_fire_delay,
+                    )
+                };
+
+                if flagger && !mine && settings.flagger_bomb_fire_delay > 0 {
+                    delay = settings.flagger_bomb_fire_delay as u16;
+                }
+
+                // Shrapnel uses the gun level.
+                let weapon = WeaponData::build(bomb_kind)
+                    .with_level(level)
+                    .with_shrapnel(gun_level.unwrap_or(0), self.shrapnel)
+                    .with_shrapnel_bouncing(self.bouncing_bullets)
+                    .with_alternate(mine);
+
+                (weapon, energy as u32 + upgrade as u32 * level as u32, delay)
+            }
+        };
+
+        let cost = if flagger {
+            cost * settings.flagger_fire_cost_percent.max(0) as u32 / 1000
+        } else {
+            cost
+        };
+
+        // Firing is never allowed to use up the last of our energy.
+        let cost = cost as i64 * 1000;
+        if self.energy <= cost {
+            return None;
+        }
+
+        self.energy -= cost;
+        self.fire_delay = delay as u32;
+
+        Some(weapon)
+    }
+
+    // Uses an item that is sent as a weapon: repel, burst, decoy or thor.
+    pub fn use_weapon_item(
+        &mut self,
+        kind: WeaponKind,
+        ship_settings: &ShipSettings,
+        settings: &ArenaSettings,
+    ) -> Option<WeaponData> {
+        let count = match kind {
+            WeaponKind::Repel if self.repel_ticks == 0 => &mut self.items.repels,
+            WeaponKind::Burst => &mut self.items.bursts,
+            WeaponKind::Decoy => &mut self.items.decoys,
+            WeaponKind::Thor => {
+                // Thors are fired like bombs, so they wait on the fire delay and afterburners.
+                if self.fire_delay > 0
+                    || (ship_settings.disable_fast_shooting && self.afterburner_active)
+                {
+                    return None;
+                }
+
+                &mut self.items.thors
+            }
+            _ => return None,
+        };
+
+        if *count == 0 {
+            return None;
+        }
+
+        *count -= 1;
+
+        match kind {
+            WeaponKind::Repel => self.repel_ticks = settings.repel_time.max(0) as u32,
+            WeaponKind::Thor => self.fire_delay = ship_settings.bomb_fire_delay as u32,
+            _ => {}
+        }
+
+        Some(WeaponData::build(kind))
+    }
+
+    pub fn use_rocket(&mut self, ship_settings: &ShipSettings) -> bool {
+        if self.rocket_ticks > 0 || self.items.rockets == 0 {
+            return false;
+        }
+
+        self.items.rockets -= 1;
+        self.rocket_ticks = ship_settings.rocket_time as u32;
+
+        true
+    }
+
+    // Takes a brick from the inventory. The caller sends the drop to the server.
+    pub fn use_brick(&mut self) -> bool {
+        if self.items.bricks == 0 {
+            return false;
+        }
+
+        self.items.bricks -= 1;
+        true
+    }
+
+    // Places a portal where the ship is. It lasts for the arena's warp point delay.
+    pub fn place_portal(&mut self, settings: &ArenaSettings) -> bool {
+        if self.items.portals == 0 {
+            return false;
+        }
+
+        self.items.portals -= 1;
+        self.portal = Some((self.pixel_x(), self.pixel_y()));
+        self.portal_ticks = settings.warp_point_delay.max(0) as u32;
+
+        true
+    }
+
+    // Warps to the placed portal, or to the given spawn position if there isn't one.
+    // Warping without a portal needs full energy.
+    pub fn warp(&mut self, spawn_x: u32, spawn_y: u32) -> bool {
+        let (x, y) = match self.portal.take() {
+            Some(portal) => {
+                self.portal_ticks = 0;
+                portal
+            }
+            None if self.energy >= self.max_energy as i64 * 1000 => (spawn_x, spawn_y),
+            None => return false,
+        };
+
+        self.set_position(x, y);
+        self.status |= StatusFlags::Flash;
+
+        true
+    }
+
+    // Applies a prize given to us. Negative prizes take the upgrade or item away.
+    pub fn apply_prize(&mut self, prize: Prize, negative: bool, ship_settings: &ShipSettings) {
+        fn adjust(value: u8, negative: bool, max: u8) -> u8 {
+            if negative {
+                value.saturating_sub(1)
+            } else {
+                value.saturating_add(1).min(max)
+            }
+        }
+
+        fn upgrade(value: u32, negative: bool, amount: u16, initial: u16, max: u16) -> u32 {
+            if negative {
+                value.saturating_sub(amount as u32).max(initial as u32)
+            } else {
+                (value + amount as u32).min(max as u32)
+            }
+        }
+
+        let ship = ship_settings;
+
+        match prize {
+            Prize::Recharge => {
+                self.recharge = upgrade(
+                    self.recharge,
+                    negative,
+                    ship.upgrade_recharge,
+                    ship.initial_recharge,
+                    ship.maximum_recharge,
+                )
+            }
+            Prize::Energy => {
+                self.max_energy = upgrade(
+                    self.max_energy,
+                    negative,
+                    ship.upgrade_energy,
+                    ship.initial_energy,
+                    ship.maximum_energy,
+                );
+                self.energy = self.energy.min(self.max_energy as i64 * 1000);
+            }
+            Prize::Rotation => {
+                self.rotation_rate = upgrade(
+                    self.rotation_rate,
+                    negative,
+                    ship.upgrade_rotation,
+                    ship.initial_rotation,
+                    ship.maximum_rotation,
+                )
+            }
+            Prize::Thruster => {
+                self.thrust = upgrade(
+                    self.thrust,
+                    negative,
+                    ship.upgrade_thrust,
+                    ship.initial_thrust,
+                    ship.maximum_thrust,
+                )
+            }
+            Prize::TopSpeed => {
+                self.speed = upgrade(
+                    self.speed,
+                    negative,
+                    ship.upgrade_speed,
+                    ship.initial_speed,
+                    ship.maximum_speed,
+                )
+            }
+            Prize::FullCharge if !negative => self.energy = self.max_energy as i64 * 1000,
+            Prize::Gun => self.guns = adjust(self.guns, negative, ship.max_guns),
+            Prize::Bomb => self.bombs = adjust(self.bombs, negative, ship.max_bombs),
+            Prize::BouncingBullets => self.bouncing_bullets = !negative,
+            Prize::Multifire => self.multifire = !negative,
+            Prize::Proximity => self.proximity = !negative,
+            Prize::Shrapnel => {
+                self.shrapnel = if negative {
+                    self.shrapnel.saturating_sub(ship.shrapnel_rate)
+                } else {
+                    self.shrapnel
+                        .saturating_add(ship.shrapnel_rate)
+                        .min(ship.max_shrapnel)
+                }
+            }
+            Prize::Repel => self.items.repels = adjust(self.items.repels, negative, ship.max_repel),
+            Prize::Burst => self.items.bursts = adjust(self.items.bursts, negative, ship.max_burst),
+            Prize::Decoy => self.items.decoys = adjust(self.items.decoys, negative, ship.max_decoy),
+            Prize::Thor => self.items.thors = adjust(self.items.thors, negative, ship.max_thor),
+            Prize::Brick => self.items.bricks = adjust(self.items.bricks, negative, ship.max_brick),
+            Prize::Rocket => {
+                self.items.rockets = adjust(self.items.rockets, negative, ship.max_rocket)
+            }
+            Prize::Portal => {
+                self.items.portals = adjust(self.items.portals, negative, ship.max_portal)
+            }
+            _ => {}
+        }
+    }
+
+    pub fn position_message(
+        &self,
+        timestamp: ServerTick,
+        weapon_info: WeaponData,
+    ) -> PositionMessage {
+        PositionMessage {
+            direction: self.direction(),
+            timestamp,
+            x_position: self.pixel_x() as u16,
+            y_position: self.pixel_y() as u16,
+            x_velocity: self.x_velocity.clamp(i16::MIN as i64, i16::MAX as i64) as i16,
+            y_velocity: self.y_velocity.clamp(i16::MIN as i64, i16::MAX as i64) as i16,
+            togglables: self.status,
+            bounty: self.bounty,
+            energy: (self.energy / 1000) as u16,
+            weapon_info,
+            extra: None,
+        }
+    }
+
+    // Lag and timers aren't tracked, so only energy and items are filled in.
+    pub fn extra_position_data(&self) -> ExtraPositionData {
+        ExtraPositionData {
+            energy: (self.energy / 1000) as u16,
+            s2c_lag: 0,
+            timer: 0,
+            items: self.items,
+        }
+    }
+}
+
+// Rotation that points along the vector, with up being zero.
+pub fn rotation_from_vector(x: f32, y: f32) -> i32 {
+    let angle = x.atan2(-y);
+    let rotation = (angle / std::f32::consts::TAU * ROTATION_PER_TURN as f32) as i32;
+
+    rotation.rem_euclid(ROTATION_PER_TURN)
+}
+
+// Shortest signed turn from one rotation to another. Positive is clockwise.
+pub fn rotation_difference(from: i32, to: i32) -> i32 {
+    let difference = (to - from).rem_euclid(ROTATION_PER_TURN);
+
+    if difference > ROTATION_PER_TURN / 2 {
+        difference - ROTATION_PER_TURN
+    } else {
+        difference
+    }
+}
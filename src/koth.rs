@@ -0,0 +1,100 @@
+use crate::clock::LocalTick;
+use crate::player::PlayerId;
+use std::collections::HashSet;
+
+// King of the hill state. Every crowned player is tracked, but the server only tells us our own timer.
+// All times are passed in so the state can be driven by any clock.
+pub struct Koth {
+    pub crowns: HashSet<PlayerId>,
+    // When our crown runs out. None when we don't have a crown.
+    pub expire_tick: Option<LocalTick>,
+}
+
+impl Koth {
+    pub fn new() -> Koth {
+        Koth {
+            crowns: HashSet::new(),
+            expire_tick: None,
+        }
+    }
+
+    pub fn has_crown(&self, player_id: PlayerId) -> bool {
+        self.crowns.contains(&player_id)
+    }
+
+    // Ticks left on our crown timer.
+    pub fn remaining(&self, now: LocalTick) -> Option<i32> {
+        self.expire_tick.map(|expire| expire.diff(&now).max(0))
+    }
+
+    pub fn set_timer(&mut self, now: LocalTick, ticks: u32) {
+        self.expire_tick = if ticks > 0 {
+            Some(now + ticks as i32)
+        } else {
+            None
+        };
+    }
+
+    pub fn add_time(&mut self, ticks: u32) {
+        if let Some(expire) = self.expire_tick {
+            self.expire_tick = Some(expire + ticks as i32);
+        }
+    }
+
+    // An invalid player id applies the change to everyone, including us.
+    pub fn on_reset(
+        &mut self,
+        now: LocalTick,
+        self_id: PlayerId,
+        player_id: PlayerId,
+        add_crown: bool,
+        timer: u32,
+        players: impl Iterator<Item = PlayerId>,
+    ) {
+        let everyone = player_id == PlayerId::invalid();
+
+        if everyone {
+            if add_crown {
+                self.crowns.extend(players);
+            } else {
+                self.crowns.clear();
+            }
+        } else if add_crown {
+            self.crowns.insert(player_id);
+        } else {
+            self.crowns.remove(&player_id);
+        }
+
+        if everyone || player_id == self_id {
+            if add_crown {
+                self.set_timer(now, timer);
+                self.crowns.insert(self_id);
+            } else {
+                self.expire_tick = None;
+            }
+        }
+    }
+
+    pub fn remove_player(&mut self, player_id: PlayerId) {
+        self.crowns.remove(&player_id);
+    }
+
+    // Returns true once when our timer runs out. The crown is removed locally and the caller
+    // is expected to tell the server.
+    pub fn update(&mut self, now: LocalTick, self_id: PlayerId) -> bool {
+        match self.expire_tick {
+            Some(expire) if now.diff(&expire) >= 0 => {
+                self.expire_tick = None;
+                self.crowns.remove(&self_id);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for Koth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
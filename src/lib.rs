@@ -0,0 +1,41 @@
+// Subspace bot library. The commonly used types are re-exported at the crate root and
+// the packet definitions are available through `net::packet` and encryption through `net::crypt`.
+// Checksums and the connection internals are private to the crate.
+
+pub mod arena_settings;
+pub mod ball;
+pub mod brick;
+pub mod chat;
+pub mod chat_log;
+pub(crate) mod checksum;
+pub mod client;
+pub mod clock;
+pub mod command;
+pub mod config;
+pub mod door;
+pub mod event;
+pub mod flag;
+pub mod flight;
+pub mod koth;
+pub mod map;
+pub mod math;
+pub mod net;
+pub mod observer;
+pub mod player;
+pub mod prediction;
+pub mod prize;
+pub mod ship;
+pub mod weapon;
+
+pub use arena_settings::{ArenaSettings, ShipSettings};
+pub use client::Client;
+pub use clock::{LocalTick, ServerTick};
+pub use command::{AccessLevel, Command, CommandRouter};
+pub use event::BotEvent;
+pub use flight::{MovementGoal, ShipInput, ShipState};
+pub use map::Map;
+pub use net::connection::{Connection, ConnectionState};
+pub use player::{Player, PlayerId, PlayerManager};
+pub use prediction::{PositionHistory, PositionSample};
+pub use ship::Ship;
+pub use weapon::{FireKind, WeaponData, WeaponKind};
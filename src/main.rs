@@ -1,18 +1,61 @@
-use crate::net::packet::c2s::RegistrationSex;
-use crate::{client::Client, net::packet::c2s::RegistrationFormMessage};
-use ctrlc;
+use anyhow::anyhow;
+use puppet::chat_log::ChatLogger;
+use puppet::config::{self, Config, Profile};
+use puppet::net::packet::c2s::SendChatMessage;
+use puppet::{AccessLevel, BotEvent, Client, CommandRouter};
+use std::path::Path;
 use std::sync::mpsc::channel;
 
-pub mod arena_settings;
-pub mod checksum;
-pub mod client;
-pub mod clock;
-pub mod map;
-pub mod math;
-pub mod net;
-pub mod player;
-pub mod ship;
-pub mod weapon;
+const DEFAULT_CONFIG_PATH: &str = "puppet.ini";
+
+// Command line arguments are --key value pairs that override the same keys in the config file.
+// --config selects the config file and --profile selects a profile from it, defaulting to the first one.
+fn load_profile() -> anyhow::Result<Profile> {
+    let mut config_path = None;
+    let mut profile_name = None;
+    let mut overrides = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let key = arg
+            .strip_prefix("--")
+            .ok_or_else(|| anyhow!("unexpected argument {}", arg))?;
+        let value = args
+            .next()
+            .ok_or_else(|| anyhow!("missing value for --{}", key))?;
+
+        match key {
+            "config" => config_path = Some(value),
+            "profile" => profile_name = Some(value),
+            _ => overrides.push((key.replace('-', "_"), value)),
+        }
+    }
+
+    let config = match &config_path {
+        Some(path) => Some(Config::load(path)?),
+        None if Path::new(DEFAULT_CONFIG_PATH).exists() => Some(Config::load(DEFAULT_CONFIG_PATH)?),
+        None => None,
+    };
+
+    let mut profile = match (config, &profile_name) {
+        (Some(mut config), Some(name)) => config
+            .take_profile(name)
+            .ok_or_else(|| anyhow!("profile {} not found", name))?,
+        (Some(mut config), None) if !config.profiles.is_empty() => config.profiles.remove(0),
+        (None, Some(name)) => {
+            return Err(anyhow!("profile {} requested without a config file", name));
+        }
+        _ => Profile::new("default"),
+    };
+
+    for (key, value) in &overrides {
+        profile.set(key, value)?;
+    }
+
+    profile.resolve_password()?;
+
+    Ok(profile)
+}
 
 fn main() -> anyhow::Result<()> {
     let (tx, rx) = channel();
@@ -21,32 +64,106 @@ fn main() -> anyhow::Result<()> {
         let _ = tx.send(());
     });
 
-    // TODO: Load these from config
-    let username = "test";
-    let password = "none";
-    let zone = "local";
-    let remote_ip = "127.0.0.1";
-    let remote_port = 5000;
-
-    let registration = RegistrationFormMessage::new(
-        "puppet",
-        "puppet@puppet.com",
-        "puppet city",
-        "puppet state",
-        RegistrationSex::Female,
-        20,
-    );
+    let profile = load_profile()?;
 
     let mut client = Client::new(
-        username,
-        password,
-        zone,
-        remote_ip,
-        remote_port,
-        registration,
+        &profile.username,
+        &profile.password,
+        &profile.zone,
+        &profile.remote_ip,
+        profile.remote_port,
+        profile.encryption,
+        profile.registration,
     )?;
 
-    client.run(rx)?;
+    client.arena_request = profile.arena;
+    client.ship = profile.ship;
+
+    if profile.chat_log {
+        client.chat_logger = Some(ChatLogger::new(&profile.zone, profile.chat_log_arena));
+    }
+
+    let mut commands = CommandRouter::new("!");
+
+    for name in &profile.owners {
+        commands.access_list.set(name, AccessLevel::Owner);
+    }
+
+    for name in &profile.mods {
+        commands.access_list.set(name, AccessLevel::Mod);
+    }
+
+    commands.register(
+        "status",
+        AccessLevel::User,
+        "Shows the bot status",
+        |client, command| {
+            let text = format!(
+                "Map: {}  Players: {}",
+                client.map.filename,
+                client.player_manager.players.len()
+            );
+            command.reply(client, &text)
+        },
+    );
+
+    commands.register(
+        "say",
+        AccessLevel::Mod,
+        "Sends a public message",
+        |client, command| client.send_chat(&SendChatMessage::public(&command.arguments)),
+    );
+
+    commands.register(
+        "go",
+        AccessLevel::Mod,
+        "Moves the bot to another arena",
+        |client, command| {
+            let arena = config::parse_arena(&command.arguments);
+            client.join_arena(arena)
+        },
+    );
+
+    client.run(rx, |client, event| {
+        if commands.process(client, &event)? {
+            return Ok(());
+        }
+
+        match event {
+            BotEvent::Chat {
+                sender_name: Some(name),
+                message,
+                ..
+            } => {
+                println!("{}> {}", name, message);
+            }
+            BotEvent::Chat { message, .. } => {
+                println!("{}", message);
+            }
+            BotEvent::PlayerEntered(player_id) => {
+                if let Some(player) = client.player_manager.get(&player_id) {
+                    println!("{} entered arena {:?}", player.name, player.ship);
+                }
+            }
+            BotEvent::PlayerLeft { name, .. } => {
+                println!("{} left arena", name);
+            }
+            BotEvent::Kill { killer, killed, .. } => {
+                let killer = client.player_manager.get(&killer);
+                let killed = client.player_manager.get(&killed);
+
+                if let (Some(killer), Some(killed)) = (killer, killed) {
+                    println!("{} killed by {}", killed.name, killer.name);
+                }
+            }
+            BotEvent::LoginResult(response) => {
+                println!("Got password response: {}", response);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    })?;
 
     Ok(())
 }
@@ -1,3 +1,6 @@
+use crate::clock::ServerTick;
+use crate::door::Doors;
+
 pub type TileId = u8;
 
 pub const TILE_ID_FIRST_DOOR: TileId = 162;
@@ -11,6 +14,8 @@ pub struct Map {
     pub checksum: u32,
     pub filename: String,
     pub tiles: Box<[TileId; 1024 * 1024]>,
+    // Top left tile of each wormhole.
+    pub wormholes: Vec<(u16, u16)>,
 }
 
 impl Map {
@@ -19,6 +24,7 @@ impl Map {
             checksum,
             filename: filename.to_owned(),
             tiles: vec![0; 1024 * 1024].into_boxed_slice().try_into().unwrap(),
+            wormholes: Vec::new(),
         };
 
         let mut position: usize = 0;
@@ -46,6 +52,10 @@ impl Map {
             let index = y as usize * 1024 + x as usize;
             map.tiles[index] = tile_id;
 
+            if tile_id == TILE_ID_WORMHOLE {
+                map.wormholes.push((x as u16, y as u16));
+            }
+
             position += 4;
         }
 
@@ -57,6 +67,7 @@ impl Map {
             checksum,
             filename: filename.to_owned(),
             tiles: vec![0; 1024 * 1024].into_boxed_slice().try_into().unwrap(),
+            wormholes: Vec::new(),
         }
     }
 
@@ -97,6 +108,18 @@ impl Map {
         false
     }
 
+    // Solidity with doors opened or closed as they are at the given tick. Doors in modes we can't predict
+    // are left to is_solid.
+    pub fn is_solid_at(&self, x: u16, y: u16, doors: &Doors, tick: ServerTick) -> bool {
+        if self.is_door(x, y)
+            && let Some(open) = doors.is_open(self.get_tile(x, y) - TILE_ID_FIRST_DOOR, tick)
+        {
+            return !open;
+        }
+
+        self.is_solid(x, y)
+    }
+
     pub fn is_solid_empty_doors(&self, x: u16, y: u16) -> bool {
         let tile_id = self.get_tile(x, y);
 
@@ -1,9 +1,9 @@
 use crate::clock::*;
-use crate::net::crypt::VieEncrypt;
+use crate::net::crypt::{self, Encryption};
 use crate::net::packet::bi::HugeChunkCancelAckMessage;
 use crate::net::packet::bi::ReliableDataMessage;
 use crate::net::packet::bi::SyncResponseMessage;
-use crate::net::packet::c2s::EncryptionRequestMessage;
+use crate::net::packet::c2s::{EncryptionClientVersion, EncryptionRequestMessage};
 use crate::net::packet::s2c::*;
 use crate::net::packet::sequencer::*;
 use crate::net::packet::{MAX_PACKET_SIZE, Packet, Serialize};
@@ -27,23 +27,29 @@ pub enum ConnectionState {
 
 pub struct Connection {
     pub remote_addr: SocketAddr,
-    pub socket: UdpSocket,
+    pub(crate) socket: UdpSocket,
     pub state: ConnectionState,
     sequencer: PacketSequencer,
     pub tick_diff: i32,
     pub player_id: PlayerId,
-    pub crypt: VieEncrypt,
+    pub encryption_version: EncryptionClientVersion,
+    pub(crate) crypt: Box<dyn Encryption>,
 }
 
 impl Connection {
-    pub fn new(remote_ip: &str, remote_port: u16) -> Result<Self> {
+    pub fn new(
+        remote_ip: &str,
+        remote_port: u16,
+        encryption_version: EncryptionClientVersion,
+    ) -> Result<Self> {
         let remote_addr = std::net::Ipv4Addr::from_str(remote_ip)?;
         let remote_addr = SocketAddr::new(IpAddr::V4(remote_addr), remote_port);
         let socket = UdpSocket::bind("0.0.0.0:0")?;
 
         socket.set_nonblocking(true)?;
 
-        let client_key = VieEncrypt::generate_key();
+        let client_key = crypt::generate_key();
+        let crypt = crypt::create_encryption(encryption_version, client_key)?;
 
         let mut result = Self {
             remote_addr,
@@ -52,10 +58,12 @@ impl Connection {
             sequencer: PacketSequencer::new(),
             tick_diff: 0,
             player_id: PlayerId::invalid(),
-            crypt: VieEncrypt::new(client_key),
+            encryption_version,
+            crypt,
         };
 
-        let encrypt_request = EncryptionRequestMessage::new(client_key);
+        let encrypt_request =
+            EncryptionRequestMessage::with_version(client_key, encryption_version);
         result.state = ConnectionState::EncryptionHandshake;
         result.send(&encrypt_request)?;
 
@@ -80,7 +88,7 @@ impl Connection {
         self.send_reliable_packet(&message.serialize())
     }
 
-    pub fn send_packet(&mut self, packet: &Packet) -> Result<()> {
+    pub(crate) fn send_packet(&mut self, packet: &Packet) -> Result<()> {
         if packet.size == 0 {
             return Err(anyhow!("packet must not be empty"));
         }
@@ -143,7 +151,7 @@ impl Connection {
         self.send_reliable_packet(&Packet::new(data))
     }
 
-    pub fn send_reliable_packet(&mut self, packet: &Packet) -> Result<()> {
+    pub(crate) fn send_reliable_packet(&mut self, packet: &Packet) -> Result<()> {
         if packet.size == 0 {
             return Err(anyhow!("reliable packet payload must not be empty"));
         }
@@ -180,7 +188,7 @@ impl Connection {
         if let Some(packet) = packet {
             let result = ServerMessage::parse(&packet.data[..packet.size])?;
             if let Some(message) = &result {
-                self.process_packet(&message);
+                self.process_packet(&message)?;
             }
 
             return Ok(result);
@@ -190,20 +198,26 @@ impl Connection {
         let sequence_message = self.sequencer.pop_process_queue()?;
 
         if let Some(message) = &sequence_message {
-            self.process_packet(&message);
+            self.process_packet(&message)?;
             return Ok(sequence_message);
         }
 
         Ok(None)
     }
 
-    fn process_packet(&mut self, message: &ServerMessage) {
+    fn process_packet(&mut self, message: &ServerMessage) -> Result<()> {
         match message {
             ServerMessage::Core(kind) => match kind {
                 CoreServerMessage::EncryptionResponse(response) => {
                     println!("Initializing encryption with key {}", response.key);
+                    // Continuing unencrypted would only get garbage back from the server.
                     if !self.crypt.initialize(response.key) {
-                        println!("Failed to initialize vie encryption.");
+                        self.state = ConnectionState::Disconnected;
+                        return Err(anyhow!(
+                            "server rejected {:?} encryption key {}",
+                            self.encryption_version,
+                            response.key
+                        ));
                     }
                 }
                 CoreServerMessage::ReliableAck(ack) => {
@@ -275,6 +289,8 @@ impl Connection {
                 _ => {}
             },
         }
+
+        Ok(())
     }
 
     fn recv_packet(&self) -> Result<Option<Packet>> {
@@ -1,7 +1,46 @@
 use crate::clock::LocalTick;
+use crate::net::packet::c2s::EncryptionClientVersion;
 use crate::net::rand::VieRng;
+use anyhow::{Result, anyhow};
 use rand::Rng;
 
+pub trait Encryption {
+    // Called with the key from the server's encryption response. Returns false if the key is rejected.
+    fn initialize(&mut self, server_key: u32) -> bool;
+    fn encrypt(&self, pkt: &[u8], dest: &mut [u8]);
+    fn decrypt(&self, pkt: &mut [u8]);
+}
+
+// Creates the encryption that matches the client version sent in the encryption request.
+// The Continuum cipher needs a key expansion that isn't available to us, so those versions fail here
+// instead of connecting without encryption.
+pub fn create_encryption(
+    version: EncryptionClientVersion,
+    client_key: u32,
+) -> Result<Box<dyn Encryption>> {
+    match version {
+        EncryptionClientVersion::Subspace => Ok(Box::new(VieEncrypt::new(client_key))),
+        EncryptionClientVersion::ContinuumClassic | EncryptionClientVersion::Continuum => {
+            Err(anyhow!("{:?} encryption is not supported", version))
+        }
+    }
+}
+
+pub fn generate_key() -> u32 {
+    let edx = LocalTick::now().value().wrapping_mul(0xCCCCCCCD);
+
+    let r1: u32 = rand::rng().random_range(0..=65535);
+    let r2: u32 = rand::rng().random_range(0..=65535);
+
+    let mut res = (r1 << 16).wrapping_add(edx >> 3).wrapping_add(r2);
+
+    if res <= 0x7FFFFFFF {
+        res = (!res).wrapping_add(1);
+    }
+
+    res
+}
+
 pub struct VieEncrypt {
     pub session_key: u32,
     pub client_key: u32,
@@ -19,7 +58,15 @@ impl VieEncrypt {
         }
     }
 
-    pub fn initialize(&mut self, server_key: u32) -> bool {
+    fn is_valid_key(&self, server_key: u32) -> bool {
+        server_key == self.session_key
+            || server_key == self.client_key
+            || server_key == ((!self.client_key).wrapping_add(1))
+    }
+}
+
+impl Encryption for VieEncrypt {
+    fn initialize(&mut self, server_key: u32) -> bool {
         if !self.is_valid_key(server_key) {
             return false;
         }
@@ -41,7 +88,7 @@ impl VieEncrypt {
         true
     }
 
-    pub fn encrypt(&self, pkt: &[u8], dest: &mut [u8]) {
+    fn encrypt(&self, pkt: &[u8], dest: &mut [u8]) {
         if self.session_key == 0 {
             dest[..pkt.len()].copy_from_slice(pkt);
             return;
@@ -90,7 +137,7 @@ impl VieEncrypt {
         }
     }
 
-    pub fn decrypt(&self, pkt: &mut [u8]) {
+    fn decrypt(&self, pkt: &mut [u8]) {
         if self.session_key == 0 {
             return;
         }
@@ -135,25 +182,74 @@ impl VieEncrypt {
             pkt[i..i + diff].copy_from_slice(&remaining_bytes[..diff]);
         }
     }
+}
 
-    pub fn generate_key() -> u32 {
-        let edx = LocalTick::now().value().wrapping_mul(0xCCCCCCCD);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT_KEY: u32 = 0xEDCBA988;
+    const SERVER_KEY: u32 = 0x12345678;
+
+    #[test]
+    fn vie_keystream() {
+        let mut vie = VieEncrypt::new(CLIENT_KEY);
+
+        assert!(vie.initialize(SERVER_KEY));
+        assert_eq!(
+            &vie.keystream[..16],
+            &[
+                0x19, 0xEA, 0x61, 0x25, 0x8E, 0x29, 0xCE, 0x30, 0x9B, 0x53, 0x8C, 0x18, 0x2D, 0xD0,
+                0x89, 0x6E
+            ]
+        );
+    }
 
-        let r1: u32 = rand::rng().random_range(0..=65535);
-        let r2: u32 = rand::rng().random_range(0..=65535);
+    #[test]
+    fn vie_game_packet() {
+        let mut vie = VieEncrypt::new(CLIENT_KEY);
+        assert!(vie.initialize(SERVER_KEY));
 
-        let mut res = (r1 << 16).wrapping_add(edx >> 3).wrapping_add(r2);
+        let plain = [
+            0x03, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A,
+        ];
+        let expected = [
+            0x03, 0x60, 0xBE, 0x56, 0x33, 0xEB, 0x91, 0x9F, 0x0B, 0x79, 0xC8,
+        ];
 
-        if res <= 0x7FFFFFFF {
-            res = (!res).wrapping_add(1);
-        }
+        let mut encrypted = [0; 11];
+        vie.encrypt(&plain, &mut encrypted);
+        assert_eq!(encrypted, expected);
 
-        res
+        vie.decrypt(&mut encrypted);
+        assert_eq!(encrypted, plain);
     }
 
-    fn is_valid_key(&self, server_key: u32) -> bool {
-        server_key == self.session_key
-            || server_key == self.client_key
-            || server_key == ((!self.client_key).wrapping_add(1))
+    #[test]
+    fn vie_core_packet() {
+        let mut vie = VieEncrypt::new(CLIENT_KEY);
+        assert!(vie.initialize(SERVER_KEY));
+
+        let plain = [0x00, 0x03, 0x01, 0x00, 0x00, 0x00, 0x0E, 0xFF];
+        let expected = [0x00, 0x03, 0x60, 0xBC, 0x55, 0x37, 0xE0, 0x6A];
+
+        let mut encrypted = [0; 8];
+        vie.encrypt(&plain, &mut encrypted);
+        assert_eq!(encrypted, expected);
+
+        vie.decrypt(&mut encrypted);
+        assert_eq!(encrypted, plain);
+    }
+
+    #[test]
+    fn vie_rejects_invalid_key() {
+        let mut vie = VieEncrypt::new(CLIENT_KEY);
+        assert!(!vie.initialize(0x11111111));
+    }
+
+    #[test]
+    fn continuum_unsupported() {
+        assert!(create_encryption(EncryptionClientVersion::Subspace, CLIENT_KEY).is_ok());
+        assert!(create_encryption(EncryptionClientVersion::Continuum, CLIENT_KEY).is_err());
     }
 }
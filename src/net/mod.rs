@@ -1,4 +1,4 @@
-pub mod connection;
+pub(crate) mod connection;
 pub mod crypt;
 pub mod packet;
 pub mod rand;
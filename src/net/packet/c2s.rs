@@ -1,8 +1,9 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use crate::checksum::weapon_checksum;
 use crate::clock::ServerTick;
-use crate::net::packet::s2c::ChatKind;
+use crate::net::packet::s2c::{ChatKind, ExtraPositionData};
 use crate::net::packet::{Packet, Serialize};
 use crate::player::PlayerId;
 use crate::ship::Ship;
@@ -10,6 +11,7 @@ use crate::weapon::WeaponData;
 
 // Core packets
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum EncryptionClientVersion {
     Subspace,
     ContinuumClassic,
@@ -28,6 +30,10 @@ impl EncryptionRequestMessage {
             version: EncryptionClientVersion::Subspace,
         }
     }
+
+    pub fn with_version(key: u32, version: EncryptionClientVersion) -> Self {
+        Self { key, version }
+    }
 }
 
 impl Serialize for EncryptionRequestMessage {
@@ -47,12 +53,37 @@ impl Serialize for EncryptionRequestMessage {
 }
 
 // Game packets
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ArenaRequest {
     AnyPublic,
     SpecificPublic(u16),
     Name([u8; 16]),
 }
 
+impl ArenaRequest {
+    // Arena names are truncated so there is always room for the null terminator.
+    pub fn named(name: &str) -> ArenaRequest {
+        let mut arena_name = [0; 16];
+        let len = name.len().min(arena_name.len() - 1);
+
+        arena_name[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+        ArenaRequest::Name(arena_name)
+    }
+
+    // Public arenas are named by their number. The arena picked for AnyPublic isn't known until the server says.
+    pub fn arena_name(&self) -> Option<String> {
+        match self {
+            ArenaRequest::AnyPublic => None,
+            ArenaRequest::SpecificPublic(number) => Some(number.to_string()),
+            ArenaRequest::Name(name) => {
+                let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                Some(String::from_utf8_lossy(&name[..len]).into_owned())
+            }
+        }
+    }
+}
+
 // 0x01
 pub struct ArenaJoinMessage {
     pub ship: Ship,
@@ -129,6 +160,8 @@ pub struct PositionMessage {
     pub bounty: u16,
     pub energy: u16,
     pub weapon_info: WeaponData,
+    // Only sent while the server asks for it, which it does when a spectator wants to see our items.
+    pub extra: Option<ExtraPositionData>,
 }
 
 impl Serialize for PositionMessage {
@@ -149,6 +182,14 @@ impl Serialize for PositionMessage {
 
         packet.data[10] = weapon_checksum(&packet.data[..packet.size]);
 
+        if let Some(extra) = &self.extra {
+            packet = packet
+                .concat_u16(extra.energy)
+                .concat_u16(extra.s2c_lag)
+                .concat_u16(extra.timer)
+                .concat_u32(extra.items.value());
+        }
+
         packet
     }
 }
@@ -173,65 +214,54 @@ pub struct SendChatMessage<'a> {
     pub kind: ChatKind,
     pub sound: u8,
     pub target_id: PlayerId,
-    pub text: &'a str,
+    // Remote private and channel messages include their addressing prefix in the text.
+    pub text: Cow<'a, str>,
 }
 
 impl<'a> SendChatMessage<'a> {
-    pub fn public(text: &'a str) -> SendChatMessage<'a> {
+    fn new(kind: ChatKind, target_id: PlayerId, text: Cow<'a, str>) -> SendChatMessage<'a> {
         SendChatMessage {
-            kind: ChatKind::Public,
+            kind,
             sound: 0,
-            target_id: PlayerId::new(0),
+            target_id,
             text,
         }
     }
 
+    pub fn public(text: &'a str) -> SendChatMessage<'a> {
+        Self::new(ChatKind::Public, PlayerId::new(0), text.into())
+    }
+
     // target must be in the same arena
     pub fn private(target_id: PlayerId, text: &'a str) -> SendChatMessage<'a> {
-        SendChatMessage {
-            kind: ChatKind::Private,
-            sound: 0,
-            target_id,
-            text,
-        }
+        Self::new(ChatKind::Private, target_id, text.into())
     }
 
-    // text must be formatted as :target:message
-    pub fn remote_private(text: &'a str) -> SendChatMessage<'a> {
-        SendChatMessage {
-            kind: ChatKind::RemotePrivate,
-            sound: 0,
-            target_id: PlayerId::new(0),
-            text,
-        }
+    // The target can be anywhere in the zone.
+    pub fn remote_private(target_name: &str, text: &str) -> SendChatMessage<'a> {
+        let text = format!(":{}:{}", target_name, text);
+        Self::new(ChatKind::RemotePrivate, PlayerId::new(0), text.into())
+    }
+
+    // Squad messages are sent as remote private messages to #squad.
+    pub fn squad(squad: &str, text: &str) -> SendChatMessage<'a> {
+        let text = format!(":#{}:{}", squad, text);
+        Self::new(ChatKind::RemotePrivate, PlayerId::new(0), text.into())
     }
 
     pub fn team(text: &'a str) -> SendChatMessage<'a> {
-        SendChatMessage {
-            kind: ChatKind::Team,
-            sound: 0,
-            target_id: PlayerId::new(0),
-            text,
-        }
+        Self::new(ChatKind::Team, PlayerId::new(0), text.into())
     }
 
-    pub fn frequency(frequency: u16, text: &'a str) -> SendChatMessage<'a> {
-        SendChatMessage {
-            kind: ChatKind::Team,
-            sound: 0,
-            target_id: PlayerId::new(frequency),
-            text,
-        }
+    // Sent to another team in the arena. The server delivers it to the frequency of the target player.
+    pub fn frequency(target_id: PlayerId, text: &'a str) -> SendChatMessage<'a> {
+        Self::new(ChatKind::Frequency, target_id, text.into())
     }
 
-    // text must be formatted as 1;message
-    pub fn channel(text: &'a str) -> SendChatMessage<'a> {
-        SendChatMessage {
-            kind: ChatKind::Channel,
-            sound: 0,
-            target_id: PlayerId::new(0),
-            text,
-        }
+    // Channels are numbered from 1 in the order they were joined with ?chat.
+    pub fn channel(channel: u8, text: &str) -> SendChatMessage<'a> {
+        let text = format!("{};{}", channel, text);
+        Self::new(ChatKind::Channel, PlayerId::new(0), text.into())
     }
 }
 
@@ -242,7 +272,7 @@ impl<'a> Serialize for SendChatMessage<'a> {
             .concat_u8(self.kind as u8)
             .concat_u8(self.sound)
             .concat_player_id(self.target_id)
-            .concat_str(self.text)
+            .concat_str(&self.text)
     }
 }
 
@@ -340,6 +370,7 @@ impl Serialize for PasswordMessage {
         client_features |= ClientFeatures::BatchPositions;
         client_features |= ClientFeatures::WarpTo;
         client_features |= ClientFeatures::Lvz;
+        client_features |= ClientFeatures::Redirect;
 
         Packet::empty()
             .concat_u8(0x09)
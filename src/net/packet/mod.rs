@@ -5,7 +5,7 @@ use std::fmt;
 pub mod bi;
 pub mod c2s;
 pub mod s2c;
-pub mod sequencer;
+pub(crate) mod sequencer;
 
 pub const MAX_PACKET_SIZE: usize = 520;
 
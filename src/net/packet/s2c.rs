@@ -2,12 +2,14 @@ use crate::arena_settings::ArenaSettings;
 use crate::clock::ServerTick;
 use crate::net::packet::Packet;
 use crate::net::packet::bi::*;
+use crate::net::packet::c2s::ArenaRequest;
 use crate::player::PlayerId;
 use crate::ship::Ship;
 use crate::weapon::WeaponData;
 use anyhow::{Result, anyhow};
 use std::ffi::CStr;
 use std::fmt::{self, Debug};
+use std::net::Ipv4Addr;
 
 pub enum ServerMessage {
     Core(CoreServerMessage),
@@ -89,7 +91,7 @@ pub enum GameServerMessage {
     WatchDamage,                                             // 0x38
     BatchedSmallPosition(BatchedPositionMessage),            // 0x39
     BatchedLargePosition(BatchedPositionMessage),            // 0x3A
-    Redirect,                                                // 0x3B
+    Redirect(RedirectMessage),                               // 0x3B
     SelectBox,                                               // 0x3C
 }
 
@@ -131,6 +133,7 @@ pub struct PlayerLeavingMessage {
     pub player_id: PlayerId,
 }
 
+#[derive(Copy, Clone, Debug, Default)]
 pub struct ItemSet {
     pub shield_active: bool,
     pub super_active: bool,
@@ -171,8 +174,21 @@ impl ItemSet {
             portals: ((data >> 26) & 0x0F) as u8,
         }
     }
+
+    pub fn value(&self) -> u32 {
+        (self.shield_active as u32)
+            | (self.super_active as u32) << 1
+            | ((self.bursts & 0x0F) as u32) << 2
+            | ((self.repels & 0x0F) as u32) << 6
+            | ((self.thors & 0x0F) as u32) << 10
+            | ((self.bricks & 0x0F) as u32) << 14
+            | ((self.decoys & 0x0F) as u32) << 18
+            | ((self.rockets & 0x0F) as u32) << 22
+            | ((self.portals & 0x0F) as u32) << 26
+    }
 }
 
+#[derive(Copy, Clone, Debug)]
 pub struct ExtraPositionData {
     pub energy: u16,
     pub s2c_lag: u16,
@@ -206,7 +222,7 @@ pub struct PlayerDeathMessage {
     pub flag_transfer: u16,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ChatKind {
     Arena = 0,
     PublicMacro = 1,
@@ -246,7 +262,7 @@ pub struct ScoreUpdateMessage {
     pub deaths: u16,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum LoginResponse {
     Ok,
     Unregistered,
@@ -496,7 +512,7 @@ pub struct PowerballPositionMessage {
     pub timestamp: ServerTick,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ArenaDirectoryEntry {
     pub name: String,
     pub count: u16,
@@ -551,6 +567,27 @@ pub struct BatchedPositionMessage {
     pub positions: Vec<BatchedPosition>,
 }
 
+// 0x3B
+pub struct RedirectMessage {
+    pub ip: Ipv4Addr,
+    pub port: u16,
+    // Same values as the arena number in the arena join packet.
+    // -1 = any public, -3 = named arena, otherwise a specific public arena.
+    pub arena_type: i16,
+    pub arena_name: String,
+    pub login_id: u32,
+}
+
+impl RedirectMessage {
+    pub fn arena_request(&self) -> ArenaRequest {
+        match self.arena_type {
+            -3 => ArenaRequest::named(&self.arena_name),
+            number if number >= 0 => ArenaRequest::SpecificPublic(number as u16),
+            _ => ArenaRequest::AnyPublic,
+        }
+    }
+}
+
 impl ServerMessage {
     pub fn parse(packet: &[u8]) -> Result<Option<ServerMessage>> {
         if packet.len() <= 0 {
@@ -1294,8 +1331,8 @@ impl ServerMessage {
 
                 while data.len() >= 4 {
                     let reward = FlagReward {
-                        frequency: u16::from_le_bytes(packet[..2].try_into().unwrap()),
-                        points: u16::from_le_bytes(packet[2..4].try_into().unwrap()),
+                        frequency: u16::from_le_bytes(data[..2].try_into().unwrap()),
+                        points: u16::from_le_bytes(data[2..4].try_into().unwrap()),
                     };
 
                     rewards.push(reward);
@@ -1722,7 +1759,27 @@ impl ServerMessage {
                 )));
             }
             0x3B => {
-                return Ok(Some(ServerMessage::Game(GameServerMessage::Redirect)));
+                if packet.len() < 29 {
+                    return Err(anyhow!("redirect message was too small"));
+                }
+
+                let ip: [u8; 4] = packet[1..5].try_into().unwrap();
+                // Names that fill all 16 bytes have no terminator.
+                let name = &packet[9..25];
+                let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                let arena_name = String::from_utf8_lossy(&name[..len]);
+
+                let message = RedirectMessage {
+                    ip: Ipv4Addr::from(ip),
+                    port: u16::from_le_bytes(packet[5..7].try_into().unwrap()),
+                    arena_type: i16::from_le_bytes(packet[7..9].try_into().unwrap()),
+                    arena_name: arena_name.into_owned(),
+                    login_id: u32::from_le_bytes(packet[25..29].try_into().unwrap()),
+                };
+
+                return Ok(Some(ServerMessage::Game(GameServerMessage::Redirect(
+                    message,
+                ))));
             }
             0x3C => {
                 return Ok(Some(ServerMessage::Game(GameServerMessage::SelectBox)));
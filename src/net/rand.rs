@@ -1,3 +1,4 @@
+#[derive(Copy, Clone)]
 pub struct VieRng {
     pub seed: i32,
 }
@@ -0,0 +1,82 @@
+use crate::clock::LocalTick;
+use crate::player::{PlayerId, PlayerManager};
+use crate::ship::Ship;
+
+// Rotates which player we spectate so the server keeps sending us positions for every ship in the arena.
+// The player with the oldest position data is picked next.
+pub struct Observer {
+    pub enabled: bool,
+    // How long each target is watched before moving to the next one.
+    pub rotate_ticks: i32,
+    pub target: Option<PlayerId>,
+
+    last_rotate_tick: LocalTick,
+}
+
+impl Observer {
+    pub fn new(rotate_ticks: i32) -> Observer {
+        Observer {
+            enabled: true,
+            rotate_ticks,
+            target: None,
+            last_rotate_tick: LocalTick::now(),
+        }
+    }
+
+    // Returns the player that should be spectated now if it is different from the current target.
+    pub fn update(&mut self, now: LocalTick, player_manager: &PlayerManager) -> Option<PlayerId> {
+        if !self.enabled {
+            return None;
+        }
+
+        let target_valid = self.target.is_some_and(|id| is_active(player_manager, id));
+
+        if target_valid && now.diff(&self.last_rotate_tick) < self.rotate_ticks {
+            return None;
+        }
+
+        let next = player_manager
+            .players
+            .values()
+            .filter(|player| player.ship != Ship::Spectator && Some(player.id) != self.target)
+            .min_by(|a, b| {
+                a.last_position_timestamp
+                    .diff(&b.last_position_timestamp)
+                    .cmp(&0)
+            })
+            .map(|player| player.id);
+
+        self.last_rotate_tick = now;
+
+        match next {
+            Some(next) => {
+                self.target = Some(next);
+                Some(next)
+            }
+            None => {
+                // Nobody else to watch, so keep the current target if it is still in a ship.
+                if !target_valid {
+                    self.target = None;
+                }
+                None
+            }
+        }
+    }
+
+    // Drops the target when it leaves the arena or spectates so a new one is picked on the next update.
+    pub fn remove_target(&mut self, player_id: PlayerId) {
+        if self.target == Some(player_id) {
+            self.target = None;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.target = None;
+    }
+}
+
+fn is_active(player_manager: &PlayerManager, player_id: PlayerId) -> bool {
+    player_manager
+        .get(&player_id)
+        .is_some_and(|player| player.ship != Ship::Spectator)
+}
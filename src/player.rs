@@ -1,9 +1,11 @@
 use crate::ship::Ship;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use crate::{
     clock::ServerTick,
     math::{Position, Velocity},
+    net::packet::s2c::ItemSet,
+    prediction::PositionHistory,
 };
 
 #[allow(nonstandard_style)]
@@ -18,7 +20,7 @@ pub mod StatusFlags {
     pub const Inert: u8 = 1 << 7;
 }
 
-#[derive(PartialEq, Clone, Copy, Eq, Hash)]
+#[derive(PartialEq, Clone, Copy, Eq, Hash, Debug)]
 pub struct PlayerId {
     pub value: u16,
 }
@@ -39,6 +41,38 @@ impl From<u16> for PlayerId {
     }
 }
 
+// Number of bounty changes kept for each player.
+pub const BOUNTY_HISTORY_LENGTH: usize = 64;
+
+// Statistics counted from what we have seen this session, not reported by the server.
+#[derive(Clone, Debug, Default)]
+pub struct PlayerStats {
+    pub kills: u32,
+    pub deaths: u32,
+    pub kill_streak: u32,
+    pub best_kill_streak: u32,
+    // Bounty and when it was seen, oldest first. A zero entry is added on death.
+    pub bounty_history: VecDeque<(ServerTick, u16)>,
+}
+
+impl PlayerStats {
+    pub fn record_bounty(&mut self, timestamp: ServerTick, bounty: u16) {
+        if self
+            .bounty_history
+            .back()
+            .is_some_and(|&(_, last)| last == bounty)
+        {
+            return;
+        }
+
+        if self.bounty_history.len() >= BOUNTY_HISTORY_LENGTH {
+            self.bounty_history.pop_front();
+        }
+
+        self.bounty_history.push_back((timestamp, bounty));
+    }
+}
+
 pub struct Player {
     pub id: PlayerId,
 
@@ -56,13 +90,33 @@ pub struct Player {
     pub status: u8,
     pub ping: u8,
 
+    // Only known when the server sends extra position data, such as while we spectate the player.
+    pub energy: Option<u16>,
+    pub items: Option<ItemSet>,
+
     pub attach_parent: PlayerId,
+    // Players attached to this one as turrets.
+    pub turrets: Vec<PlayerId>,
     pub flag_count: u16,
 
+    // Score as kept by the server. Wins and losses are the kills and deaths shown on the scoreboard.
+    pub kill_points: u32,
+    pub flag_points: u32,
+    pub wins: u16,
+    pub losses: u16,
+
+    pub stats: PlayerStats,
+
     pub last_position_timestamp: ServerTick,
+    // Recent position updates, used for interpolation and tracking how well we predict this player.
+    pub history: PositionHistory,
 }
 
 impl Player {
+    pub fn score(&self) -> u32 {
+        self.kill_points + self.flag_points
+    }
+
     pub fn new(id: PlayerId, name: &str, squad: &str, ship: Ship, frequency: u16) -> Self {
         Self {
             id,
@@ -79,10 +133,22 @@ impl Player {
             status: 0,
             ping: 0,
 
+            energy: None,
+            items: None,
+
             attach_parent: PlayerId::invalid(),
+            turrets: Vec::new(),
             flag_count: 0,
 
+            kill_points: 0,
+            flag_points: 0,
+            wins: 0,
+            losses: 0,
+
+            stats: PlayerStats::default(),
+
             last_position_timestamp: ServerTick::empty(),
+            history: PositionHistory::default(),
         }
     }
 }
@@ -106,11 +172,193 @@ impl PlayerManager {
         self.players.get_mut(&id)
     }
 
-    pub fn add_player(&mut self, player: Player) -> Option<Player> {
-        self.players.insert(player.id, player)
+    // Links the new player into the attach graph using its attach_parent and any players already attached to it.
+    pub fn add_player(&mut self, mut player: Player) -> Option<Player> {
+        let id = player.id;
+        let parent = player.attach_parent;
+
+        player.turrets = self
+            .players
+            .values()
+            .filter(|turret| turret.attach_parent == id)
+            .map(|turret| turret.id)
+            .collect();
+
+        let old_player = self.players.insert(id, player);
+
+        if let Some(parent) = self.players.get_mut(&parent)
+            && !parent.turrets.contains(&id)
+        {
+            parent.turrets.push(id);
+        }
+
+        old_player
     }
 
     pub fn remove_player(&mut self, id: &PlayerId) -> Option<Player> {
+        self.detach_all(id);
         self.players.remove(id)
     }
+
+    pub fn turrets_of(&self, id: &PlayerId) -> &[PlayerId] {
+        self.players
+            .get(id)
+            .map(|player| player.turrets.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn attach(&mut self, turret_id: &PlayerId, parent_id: &PlayerId) {
+        self.detach(turret_id);
+
+        if !self.players.contains_key(turret_id) {
+            return;
+        }
+
+        let Some(parent) = self.players.get_mut(parent_id) else {
+            return;
+        };
+
+        parent.turrets.push(*turret_id);
+
+        if let Some(turret) = self.players.get_mut(turret_id) {
+            turret.attach_parent = *parent_id;
+        }
+    }
+
+    // Detaches the player from whoever they are attached to.
+    pub fn detach(&mut self, turret_id: &PlayerId) {
+        let Some(turret) = self.players.get_mut(turret_id) else {
+            return;
+        };
+
+        let parent_id = turret.attach_parent;
+        turret.attach_parent = PlayerId::invalid();
+
+        if let Some(parent) = self.players.get_mut(&parent_id) {
+            parent.turrets.retain(|id| id != turret_id);
+        }
+    }
+
+    // Detaches every turret from the player.
+    pub fn detach_turrets(&mut self, parent_id: &PlayerId) {
+        let Some(parent) = self.players.get_mut(parent_id) else {
+            return;
+        };
+
+        for turret_id in std::mem::take(&mut parent.turrets) {
+            if let Some(turret) = self.players.get_mut(&turret_id) {
+                turret.attach_parent = PlayerId::invalid();
+            }
+        }
+    }
+
+    // Ship changes and leaving break every link the player has.
+    pub fn detach_all(&mut self, id: &PlayerId) {
+        self.detach(id);
+        self.detach_turrets(id);
+    }
+
+    // Turrets ride along with their parent, so they are given the parent's movement.
+    pub fn move_turrets(&mut self, parent_id: &PlayerId) {
+        let Some(parent) = self.players.get(parent_id) else {
+            return;
+        };
+
+        let position = parent.position;
+        let velocity = parent.velocity;
+        let timestamp = parent.last_position_timestamp;
+        let turrets = parent.turrets.clone();
+
+        for turret_id in turrets {
+            if let Some(turret) = self.players.get_mut(&turret_id) {
+                turret.position = position;
+                turret.velocity = velocity;
+                turret.last_position_timestamp = timestamp;
+            }
+        }
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&Player> {
+        self.players
+            .values()
+            .find(|player| player.name.eq_ignore_ascii_case(name))
+    }
+
+    // Any player on the frequency, used as the target of frequency chat messages.
+    pub fn get_by_frequency(&self, frequency: u16) -> Option<&Player> {
+        self.players
+            .values()
+            .find(|player| player.frequency == frequency)
+    }
+
+    // Players ordered by total points, highest first.
+    pub fn by_score(&self) -> Vec<&Player> {
+        let mut players: Vec<&Player> = self.players.values().collect();
+        players.sort_by_key(|player| std::cmp::Reverse(player.score()));
+        players
+    }
+
+    pub fn on_score_update(
+        &mut self,
+        id: &PlayerId,
+        kill_points: u32,
+        flag_points: u32,
+        wins: u16,
+        losses: u16,
+    ) {
+        if let Some(player) = self.players.get_mut(id) {
+            player.kill_points = kill_points;
+            player.flag_points = flag_points;
+            player.wins = wins;
+            player.losses = losses;
+        }
+    }
+
+    // The server doesn't send score updates for kills, so the points are added the same way the game client does.
+    pub fn on_death(
+        &mut self,
+        killer_id: &PlayerId,
+        killed_id: &PlayerId,
+        points: u32,
+        timestamp: ServerTick,
+    ) {
+        if let Some(killer) = self.players.get_mut(killer_id) {
+            killer.kill_points += points;
+            killer.wins = killer.wins.wrapping_add(1);
+
+            killer.stats.kills += 1;
+            killer.stats.kill_streak += 1;
+            killer.stats.best_kill_streak =
+                killer.stats.best_kill_streak.max(killer.stats.kill_streak);
+        }
+
+        if let Some(killed) = self.players.get_mut(killed_id) {
+            killed.losses = killed.losses.wrapping_add(1);
+            killed.bounty = 0;
+
+            killed.stats.deaths += 1;
+            killed.stats.kill_streak = 0;
+            killed.stats.record_bounty(timestamp, 0);
+        }
+    }
+
+    // Resets the score of one player, or everyone when the id is invalid.
+    pub fn reset_score(&mut self, id: &PlayerId) {
+        for player in self.players.values_mut() {
+            if *id == PlayerId::invalid() || player.id == *id {
+                player.kill_points = 0;
+                player.flag_points = 0;
+                player.wins = 0;
+                player.losses = 0;
+            }
+        }
+    }
+
+    pub fn add_flag_points(&mut self, frequency: u16, points: u32) {
+        for player in self.players.values_mut() {
+            if player.frequency == frequency && player.ship != Ship::Spectator {
+                player.flag_points += points;
+            }
+        }
+    }
 }
@@ -0,0 +1,200 @@
+use crate::clock::ServerTick;
+use crate::map::Map;
+use crate::math::{Position, Velocity};
+use std::collections::VecDeque;
+
+// Number of position updates kept for each player.
+pub const POSITION_HISTORY_LENGTH: usize = 32;
+// Predictions are limited to this many ticks past the last update. Anything older is too stale to be useful.
+pub const MAX_PREDICTION_TICKS: i32 = 500;
+// Ship radius used when the arena's ship settings leave it at zero.
+pub const DEFAULT_SHIP_RADIUS: u16 = 14;
+// A bounce factor of 16 keeps all speed when hitting a wall. Higher factors lose more of it.
+pub const BOUNCE_FACTOR_BASE: i64 = 16;
+
+#[derive(Copy, Clone, Debug)]
+pub struct PositionSample {
+    pub timestamp: ServerTick,
+    pub position: Position,
+    pub velocity: Velocity,
+}
+
+// Distance in pixels between where we predicted a player would be and where the next update put them.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PredictionError {
+    pub count: u32,
+    pub last: f32,
+    pub max: f32,
+    pub total: f64,
+}
+
+impl PredictionError {
+    pub fn record(&mut self, predicted: Position, actual: Position) {
+        let dx = predicted.x as f32 - actual.x as f32;
+        let dy = predicted.y as f32 - actual.y as f32;
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        self.count += 1;
+        self.last = distance;
+        self.max = self.max.max(distance);
+        self.total += distance as f64;
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        (self.total / self.count as f64) as f32
+    }
+}
+
+// Recent position updates for one player, oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct PositionHistory {
+    pub samples: VecDeque<PositionSample>,
+    pub error: PredictionError,
+}
+
+impl PositionHistory {
+    // Adds an update and records the error of the prediction that was made for it, if there was one.
+    pub fn push(&mut self, sample: PositionSample, predicted: Option<Position>) {
+        if let Some(predicted) = predicted {
+            self.error.record(predicted, sample.position);
+        }
+
+        if self.samples.len() >= POSITION_HISTORY_LENGTH {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    pub fn latest(&self) -> Option<&PositionSample> {
+        self.samples.back()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    // Position between the two updates around the tick. Ticks after the newest update need prediction instead.
+    pub fn interpolate(&self, tick: ServerTick) -> Option<Position> {
+        let newest = self.samples.back()?;
+
+        if tick.diff(&newest.timestamp) >= 0 {
+            return None;
+        }
+
+        let next_index = self
+            .samples
+            .iter()
+            .position(|sample| sample.timestamp.diff(&tick) > 0)?;
+
+        let next = &self.samples[next_index];
+        if next_index == 0 {
+            return Some(next.position);
+        }
+
+        let previous = &self.samples[next_index - 1];
+        let span = next.timestamp.diff(&previous.timestamp);
+        if span <= 0 {
+            return Some(next.position);
+        }
+
+        let t = tick.diff(&previous.timestamp) as i64;
+        let span = span as i64;
+        let x = previous.position.x as i64
+            + (next.position.x as i64 - previous.position.x as i64) * t / span;
+        let y = previous.position.y as i64
+            + (next.position.y as i64 - previous.position.y as i64) * t / span;
+
+        Some(Position::new(x as u32, y as u32))
+    }
+}
+
+// Advances the sample to the tick by its velocity, bouncing off walls the same way ships do.
+// Velocity is in pixels per 10 seconds, so each tick moves velocity thousandths of a pixel.
+pub(crate) fn predict(
+    map: &Map,
+    sample: &PositionSample,
+    tick: ServerTick,
+    radius: u16,
+    bounce_factor: i16,
+) -> PositionSample {
+    let ticks = tick.diff(&sample.timestamp).clamp(0, MAX_PREDICTION_TICKS);
+
+    let mut x = sample.position.x as i64 * 1000;
+    let mut y = sample.position.y as i64 * 1000;
+    let mut x_velocity = sample.velocity.x as i64;
+    let mut y_velocity = sample.velocity.y as i64;
+
+    for _ in 0..ticks {
+        if x_velocity == 0 && y_velocity == 0 {
+            break;
+        }
+
+        // Each axis is moved separately so sliding along a wall keeps the other axis moving.
+        if collides(map, x + x_velocity, y, radius) {
+            x_velocity = -bounce(x_velocity, bounce_factor);
+            y_velocity = bounce(y_velocity, bounce_factor);
+        } else {
+            x += x_velocity;
+        }
+
+        if collides(map, x, y + y_velocity, radius) {
+            x_velocity = bounce(x_velocity, bounce_factor);
+            y_velocity = -bounce(y_velocity, bounce_factor);
+        } else {
+            y += y_velocity;
+        }
+    }
+
+    PositionSample {
+        timestamp: sample.timestamp + ticks,
+        position: Position::new((x / 1000) as u32, (y / 1000) as u32),
+        velocity: Velocity::new(x_velocity as i32, y_velocity as i32),
+    }
+}
+
+// Velocity left after a ship hits a wall. Factors of zero or less aren't valid, so they keep all of the speed.
+pub(crate) fn bounce(velocity: i64, bounce_factor: i16) -> i64 {
+    if bounce_factor <= 0 {
+        return velocity;
+    }
+
+    velocity * BOUNCE_FACTOR_BASE / bounce_factor as i64
+}
+
+// Whether a ship centered at the position touches any solid tile. Positions are in thousandths of a pixel
+// and anything outside the map counts as solid.
+pub(crate) fn collides(map: &Map, x: i64, y: i64, radius: u16) -> bool {
+    collides_with(x, y, radius, |tile_x, tile_y| map.is_solid(tile_x, tile_y))
+}
+
+// Same as collides, but with solidity decided by the caller so doors and bricks can be included.
+pub(crate) fn collides_with<F>(x: i64, y: i64, radius: u16, is_solid: F) -> bool
+where
+    F: Fn(u16, u16) -> bool,
+{
+    let radius = radius as i64 * 1000;
+
+    let start_x = (x - radius).div_euclid(16000);
+    let end_x = (x + radius - 1).div_euclid(16000);
+    let start_y = (y - radius).div_euclid(16000);
+    let end_y = (y + radius - 1).div_euclid(16000);
+
+    for tile_y in start_y..=end_y {
+        for tile_x in start_x..=end_x {
+            if !(0..1024).contains(&tile_x) || !(0..1024).contains(&tile_y) {
+                return true;
+            }
+
+            if is_solid(tile_x as u16, tile_y as u16) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
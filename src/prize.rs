@@ -0,0 +1,68 @@
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Prize {
+    Recharge = 1,
+    Energy,
+    Rotation,
+    Stealth,
+    Cloak,
+    XRadar,
+    Warp,
+    Gun,
+    Bomb,
+    BouncingBullets,
+    Thruster,
+    TopSpeed,
+    FullCharge,
+    EngineShutdown,
+    Multifire,
+    Proximity,
+    Super,
+    Shields,
+    Shrapnel,
+    Antiwarp,
+    Repel,
+    Burst,
+    Decoy,
+    Thor,
+    Multiprize,
+    Brick,
+    Rocket,
+    Portal,
+}
+
+impl Prize {
+    pub fn from_id(id: u8) -> Option<Prize> {
+        PRIZES.get((id as usize).wrapping_sub(1)).copied()
+    }
+}
+
+const PRIZES: [Prize; 28] = [
+    Prize::Recharge,
+    Prize::Energy,
+    Prize::Rotation,
+    Prize::Stealth,
+    Prize::Cloak,
+    Prize::XRadar,
+    Prize::Warp,
+    Prize::Gun,
+    Prize::Bomb,
+    Prize::BouncingBullets,
+    Prize::Thruster,
+    Prize::TopSpeed,
+    Prize::FullCharge,
+    Prize::EngineShutdown,
+    Prize::Multifire,
+    Prize::Proximity,
+    Prize::Super,
+    Prize::Shields,
+    Prize::Shrapnel,
+    Prize::Antiwarp,
+    Prize::Repel,
+    Prize::Burst,
+    Prize::Decoy,
+    Prize::Thor,
+    Prize::Multiprize,
+    Prize::Brick,
+    Prize::Rocket,
+    Prize::Portal,
+];
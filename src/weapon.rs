@@ -1,4 +1,14 @@
-#[derive(Debug)]
+use crate::arena_settings::ArenaSettings;
+use crate::clock::ServerTick;
+use crate::math::{Position, Velocity};
+use crate::net::packet::s2c::LargePositionMessage;
+use crate::player::PlayerId;
+use crate::ship::Ship;
+
+// Projectiles are never simulated further than this in one update, so a bad timestamp can't stall the client.
+const MAX_SIMULATION_TICKS: i32 = 1000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum WeaponKind {
     None = 0,
     Bullet,
@@ -24,12 +34,22 @@ impl Into<WeaponKind> for u16 {
             6 => WeaponKind::Decoy,
             7 => WeaponKind::Burst,
             8 => WeaponKind::Thor,
+            15 => WeaponKind::Shrapnel,
             _ => WeaponKind::None,
         }
     }
 }
 
-#[derive(Copy, Clone)]
+// Weapons our own ship can fire.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FireKind {
+    Gun,
+    Multifire,
+    Bomb,
+    Mine,
+}
+
+#[derive(Copy, Clone, Debug)]
 pub struct WeaponData {
     pub value: u16,
 }
@@ -39,6 +59,29 @@ impl WeaponData {
         Self { value }
     }
 
+    // Starts building weapon data for the kind. Levels are zero based, so zero is an L1 weapon.
+    pub fn build(kind: WeaponKind) -> Self {
+        Self::new(kind as u16 & 0x1F)
+    }
+
+    pub fn with_level(self, level: u8) -> Self {
+        Self::new((self.value & !(0x03 << 5)) | ((level as u16 & 0x03) << 5))
+    }
+
+    pub fn with_shrapnel(self, level: u8, count: u8) -> Self {
+        let value = self.value & !(0x03 << 8) & !(0x1F << 10);
+        Self::new(value | ((level as u16 & 0x03) << 8) | ((count as u16 & 0x1F) << 10))
+    }
+
+    pub fn with_shrapnel_bouncing(self, bouncing: bool) -> Self {
+        Self::new((self.value & !(1 << 7)) | ((bouncing as u16) << 7))
+    }
+
+    // Alternate fire is multifire for bullets and a mine for bombs.
+    pub fn with_alternate(self, alternate: bool) -> Self {
+        Self::new((self.value & !(1 << 15)) | ((alternate as u16) << 15))
+    }
+
     pub fn kind(&self) -> WeaponKind {
         let kind: WeaponKind = (self.value & 0x1F).into();
         kind
@@ -85,3 +128,491 @@ impl std::fmt::Display for WeaponData {
         )
     }
 }
+
+// A weapon in flight. Positions are in thousandths of a pixel and velocity in pixels per 10 seconds.
+#[derive(Copy, Clone, Debug)]
+pub struct Projectile {
+    pub owner: PlayerId,
+    pub frequency: u16,
+    pub weapon: WeaponData,
+    pub x: i64,
+    pub y: i64,
+    pub x_velocity: i64,
+    pub y_velocity: i64,
+    // Wall bounces left before the projectile ends. None bounces until it expires.
+    pub bounces: Option<u32>,
+    // Ticks it has been simulated up to, and when it expires.
+    pub tick: ServerTick,
+    pub end_tick: ServerTick,
+}
+
+impl Projectile {
+    pub fn position(&self) -> Position {
+        Position::new((self.x / 1000) as u32, (self.y / 1000) as u32)
+    }
+
+    pub fn velocity(&self) -> Velocity {
+        Velocity::new(self.x_velocity as i32, self.y_velocity as i32)
+    }
+
+    pub fn is_mine(&self) -> bool {
+        matches!(
+            self.weapon.kind(),
+            WeaponKind::Bomb | WeaponKind::ProximityBomb
+        ) && self.weapon.alternate()
+    }
+
+    // Bombs, mines and thors explode when they end. Bullets just disappear.
+    pub fn explodes(&self) -> bool {
+        matches!(
+            self.weapon.kind(),
+            WeaponKind::Bomb | WeaponKind::ProximityBomb | WeaponKind::Thor
+        )
+    }
+}
+
+// A ship that weapons can hit or set off, at the position it's predicted to be at.
+#[derive(Copy, Clone, Debug)]
+pub struct Target {
+    pub frequency: u16,
+    pub position: Position,
+    pub radius: u16,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Explosion {
+    pub owner: PlayerId,
+    pub frequency: u16,
+    pub weapon: WeaponData,
+    pub position: Position,
+    pub tick: ServerTick,
+}
+
+// Tracks weapons fired by other players so they can be dodged or their damage estimated. Mines we place
+// ourselves are added too, since the arena limits how many can be out at once.
+pub struct WeaponManager {
+    pub projectiles: Vec<Projectile>,
+}
+
+impl WeaponManager {
+    pub fn new() -> WeaponManager {
+        WeaponManager {
+            projectiles: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.projectiles.clear();
+    }
+
+    pub fn on_player_leave(&mut self, player_id: PlayerId) {
+        self.projectiles
+            .retain(|projectile| projectile.owner != player_id);
+    }
+
+    // The server doesn't send our own weapons back to us, so our mines are added when we place them.
+    pub fn add_mine(
+        &mut self,
+        owner: PlayerId,
+        frequency: u16,
+        weapon: WeaponData,
+        position: Position,
+        timestamp: ServerTick,
+        settings: &ArenaSettings,
+    ) {
+        self.projectiles.push(Projectile {
+            owner,
+            frequency,
+            weapon,
+            x: position.x as i64 * 1000,
+            y: position.y as i64 * 1000,
+            x_velocity: 0,
+            y_velocity: 0,
+            bounces: None,
+            tick: timestamp,
+            end_tick: timestamp + settings.mine_alive_time,
+        });
+    }
+
+    pub fn mine_count(&self, owner: PlayerId) -> usize {
+        self.projectiles
+            .iter()
+            .filter(|projectile| projectile.is_mine() && projectile.owner == owner)
+            .count()
+    }
+
+    pub fn team_mine_count(&self, frequency: u16) -> usize {
+        self.projectiles
+            .iter()
+            .filter(|projectile| projectile.is_mine() && projectile.frequency == frequency)
+            .count()
+    }
+
+    // Spawns the projectiles for a weapon fired from a position packet. The timestamp is the packet's
+    // reconstructed server tick.
+    pub fn on_fire(
+        &mut self,
+        message: &LargePositionMessage,
+        ship: Ship,
+        frequency: u16,
+        timestamp: ServerTick,
+        settings: &ArenaSettings,
+    ) {
+        let owner = message.player_id;
+        let weapon = message.weapon;
+        let position = Position::new(message.x as u32, message.y as u32);
+        let velocity = Velocity::new(message.x_velocity as i32, message.y_velocity as i32);
+        let direction = message.direction;
+
+        let Some(ship_settings) = settings.ship(ship) else {
+            return;
+        };
+
+        let x = position.x as i64 * 1000;
+        let y = position.y as i64 * 1000;
+        let rotation = direction as f32 / 40.0;
+
+        let spawn =
+            |weapon: WeaponData, x: i64, y: i64, rotation: f32, speed: i64, alive: i32, bounces| {
+                let (heading_x, heading_y) = heading(rotation);
+
+                Projectile {
+                    owner,
+                    frequency,
+                    weapon,
+                    x,
+                    y,
+                    x_velocity: velocity.x as i64 + (heading_x * speed as f32) as i64,
+                    y_velocity: velocity.y as i64 + (heading_y * speed as f32) as i64,
+                    bounces,
+                    tick: timestamp,
+                    end_tick: timestamp + alive,
+                }
+            };
+
+        match weapon.kind() {
+            WeaponKind::Bullet | WeaponKind::BouncingBullet => {
+                let speed = ship_settings.bullet_speed as i64;
+                let alive = settings.bullet_alive_time;
+                let bounces = if weapon.kind() == WeaponKind::BouncingBullet {
+                    None
+                } else {
+                    Some(0)
+                };
+
+                if weapon.alternate() {
+                    // Multifire angle is in thousandths of a direction.
+                    let spread = ship_settings.multi_fire_angle as f32 / 1000.0 / 40.0;
+
+                    for offset in [-spread, 0.0, spread] {
+                        self.projectiles.push(spawn(
+                            weapon,
+                            x,
+                            y,
+                            rotation + offset,
+                            speed,
+                            alive,
+                            bounces,
+                        ));
+                    }
+                } else if ship_settings.double_barrel {
+                    // Double barrel bullets come out of each side of the ship.
+                    let (heading_x, heading_y) = heading(rotation);
+                    let offset = ship_settings.radius.max(1) as f32 * 700.0;
+                    let side_x = (-heading_y * offset) as i64;
+                    let side_y = (heading_x * offset) as i64;
+
+                    self.projectiles.push(spawn(
+                        weapon,
+                        x - side_x,
+                        y - side_y,
+                        rotation,
+                        speed,
+                        alive,
+                        bounces,
+                    ));
+                    self.projectiles.push(spawn(
+                        weapon,
+                        x + side_x,
+                        y + side_y,
+                        rotation,
+                        speed,
+                        alive,
+                        bounces,
+                    ));
+                } else {
+                    self.projectiles
+                        .push(spawn(weapon, x, y, rotation, speed, alive, bounces));
+                }
+            }
+            WeaponKind::Bomb | WeaponKind::ProximityBomb => {
+                let bounces = Some(ship_settings.bomb_bounce_count as u32);
+
+                if weapon.alternate() {
+                    // Mines stay where they are placed.
+                    let mut mine =
+                        spawn(weapon, x, y, rotation, 0, settings.mine_alive_time, bounces);
+                    mine.x_velocity = 0;
+                    mine.y_velocity = 0;
+                    self.projectiles.push(mine);
+                } else {
+                    let speed = ship_settings.bomb_speed as i64;
+                    self.projectiles.push(spawn(
+                        weapon,
+                        x,
+                        y,
+                        rotation,
+                        speed,
+                        settings.bomb_alive_time,
+                        bounces,
+                    ));
+                }
+            }
+            WeaponKind::Thor => {
+                let speed = ship_settings.bomb_speed as i64;
+                self.projectiles.push(spawn(
+                    weapon,
+                    x,
+                    y,
+                    rotation,
+                    speed,
+                    settings.bomb_alive_time,
+                    None,
+                ));
+            }
+            WeaponKind::Burst => {
+                // Burst bullets go out evenly in every direction and bounce until they expire.
+                let count = ship_settings.burst_shrapnel.max(1);
+                let speed = ship_settings.burst_speed as i64;
+
+                for i in 0..count {
+                    let mut bullet = spawn(
+                        weapon,
+                        x,
+                        y,
+                        i as f32 / count as f32,
+                        speed,
+                        settings.bullet_alive_time,
+                        None,
+                    );
+                    // Burst bullets don't carry the ship's velocity.
+                    bullet.x_velocity -= velocity.x as i64;
+                    bullet.y_velocity -= velocity.y as i64;
+                    self.projectiles.push(bullet);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Moves every projectile up to the tick. Projectiles that hit a wall, run out of time or come close enough
+    // to an enemy to trigger proximity are removed, and those that explode are returned.
+    // Solidity takes the projectile's frequency, since bricks don't block their own team.
+    pub fn update<F>(
+        &mut self,
+        now: ServerTick,
+        settings: &ArenaSettings,
+        targets: &[Target],
+        is_solid: F,
+    ) -> Vec<Explosion>
+    where
+        F: Fn(u16, u16, u16) -> bool,
+    {
+        let mut explosions = Vec::new();
+        let mut index = 0;
+
+        while index < self.projectiles.len() {
+            let projectile = &mut self.projectiles[index];
+            let ended = simulate(projectile, now, settings, targets, &is_solid);
+
+            if !ended {
+                index += 1;
+                continue;
+            }
+
+            let projectile = self.projectiles.swap_remove(index);
+
+            if projectile.explodes() {
+                let explosion = Explosion {
+                    owner: projectile.owner,
+                    frequency: projectile.frequency,
+                    weapon: projectile.weapon,
+                    position: projectile.position(),
+                    tick: projectile.tick,
+                };
+
+                self.spawn_shrapnel(&projectile, settings);
+                explosions.push(explosion);
+            }
+        }
+
+        explosions
+    }
+
+    // Shrapnel flies out evenly from where a bomb or mine exploded.
+    fn spawn_shrapnel(&mut self, projectile: &Projectile, settings: &ArenaSettings) {
+        let count = projectile.weapon.shrapnel_count();
+        if count == 0 || projectile.weapon.kind() == WeaponKind::Thor {
+            return;
+        }
+
+        let bouncing = projectile.weapon.shrapnel_bouncing();
+        let weapon = WeaponData::build(WeaponKind::Shrapnel)
+            .with_level(projectile.weapon.shrapnel_level())
+            .with_shrapnel_bouncing(bouncing);
+        let speed = settings.shrapnel_speed as f32;
+
+        for i in 0..count {
+            let (heading_x, heading_y) = heading(i as f32 / count as f32);
+
+            self.projectiles.push(Projectile {
+                owner: projectile.owner,
+                frequency: projectile.frequency,
+                weapon,
+                x: projectile.x,
+                y: projectile.y,
+                x_velocity: (heading_x * speed) as i64,
+                y_velocity: (heading_y * speed) as i64,
+                bounces: if bouncing { None } else { Some(0) },
+                tick: projectile.tick,
+                end_tick: projectile.tick + settings.bullet_alive_time,
+            });
+        }
+    }
+}
+
+impl Default for WeaponManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Unit vector for a fraction of a full turn, where zero points up.
+fn heading(turn: f32) -> (f32, f32) {
+    let angle = turn * std::f32::consts::TAU;
+    (angle.sin(), -angle.cos())
+}
+
+// Runs the projectile one tick at a time up to now. Returns true once it has ended.
+fn simulate<F>(
+    projectile: &mut Projectile,
+    now: ServerTick,
+    settings: &ArenaSettings,
+    targets: &[Target],
+    is_solid: &F,
+) -> bool
+where
+    F: Fn(u16, u16, u16) -> bool,
+{
+    let ticks = now.diff(&projectile.tick).clamp(0, MAX_SIMULATION_TICKS);
+    let passes_walls = projectile.weapon.kind() == WeaponKind::Thor;
+
+    for _ in 0..ticks {
+        if projectile.tick >= projectile.end_tick {
+            return true;
+        }
+
+        projectile.tick = projectile.tick + 1;
+
+        if triggers_proximity(projectile, settings, targets) || hits_ship(projectile, targets) {
+            return true;
+        }
+
+        let next_x = projectile.x + projectile.x_velocity;
+        let next_y = projectile.y + projectile.y_velocity;
+
+        if passes_walls {
+            projectile.x = next_x;
+            projectile.y = next_y;
+            continue;
+        }
+
+        let hit_x = is_solid_pixel(next_x, projectile.y, projectile.frequency, is_solid);
+        let hit_y = is_solid_pixel(projectile.x, next_y, projectile.frequency, is_solid);
+        let hit_corner =
+            !hit_x && !hit_y && is_solid_pixel(next_x, next_y, projectile.frequency, is_solid);
+
+        if !hit_x && !hit_y && !hit_corner {
+            projectile.x = next_x;
+            projectile.y = next_y;
+            continue;
+        }
+
+        match &mut projectile.bounces {
+            Some(0) => return true,
+            Some(bounces) => *bounces -= 1,
+            None => {}
+        }
+
+        // Unlike ships, weapons keep all of their speed when they bounce.
+        if hit_x || hit_corner {
+            projectile.x_velocity = -projectile.x_velocity;
+        } else {
+            projectile.x = next_x;
+        }
+
+        if hit_y || hit_corner {
+            projectile.y_velocity = -projectile.y_velocity;
+        } else {
+            projectile.y = next_y;
+        }
+    }
+
+    projectile.tick >= projectile.end_tick
+}
+
+// Proximity bombs and mines go off when an enemy ship is within the arena's proximity distance,
+// which grows by a tile for each bomb level.
+fn triggers_proximity(
+    projectile: &Projectile,
+    settings: &ArenaSettings,
+    targets: &[Target],
+) -> bool {
+    if projectile.weapon.kind() != WeaponKind::ProximityBomb {
+        return false;
+    }
+
+    let range = (settings.proximity_distance.max(0) as i64 + projectile.weapon.level() as i64) * 16;
+    let position = projectile.position();
+
+    targets.iter().any(|target| {
+        target.frequency != projectile.frequency
+            && (target.position.x as i64 - position.x as i64).abs() <= range
+            && (target.position.y as i64 - position.y as i64).abs() <= range
+    })
+}
+
+// Bullets and bombs end when they touch an enemy ship. Repels and decoys don't hit anything.
+fn hits_ship(projectile: &Projectile, targets: &[Target]) -> bool {
+    if matches!(
+        projectile.weapon.kind(),
+        WeaponKind::None | WeaponKind::Repel | WeaponKind::Decoy
+    ) {
+        return false;
+    }
+
+    let position = projectile.position();
+
+    targets.iter().any(|target| {
+        let radius = target.radius as i64;
+
+        target.frequency != projectile.frequency
+            && (target.position.x as i64 - position.x as i64).abs() <= radius
+            && (target.position.y as i64 - position.y as i64).abs() <= radius
+    })
+}
+
+// Positions are in thousandths of a pixel. Anything outside the map counts as solid.
+fn is_solid_pixel<F>(x: i64, y: i64, frequency: u16, is_solid: &F) -> bool
+where
+    F: Fn(u16, u16, u16) -> bool,
+{
+    let tile_x = x.div_euclid(16000);
+    let tile_y = y.div_euclid(16000);
+
+    if !(0..1024).contains(&tile_x) || !(0..1024).contains(&tile_y) {
+        return true;
+    }
+
+    is_solid(tile_x as u16, tile_y as u16, frequency)
+}